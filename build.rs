@@ -3,7 +3,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::configure()
         .build_server(false)
         .build_client(true)
-        .compile(
+        // JSON mapping for the generic JSON-RPC endpoint, using proto field names
+        .type_attribute(
+            ".",
+            "#[derive(serde::Serialize, serde::Deserialize)] #[serde(rename_all = \"camelCase\")]",
+        )
+        .message_attribute(".", "#[serde(default)]")
+        .field_attribute(
+            "protowire.SubmitBlockRequestMessage.allowNonDAABlocks",
+            "#[serde(alias = \"allowNonDAABlocks\")]",
+        )
+        .compile_protos(
             &[
                 "proto/rpc.proto",
                 "proto/messages.proto",
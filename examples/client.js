@@ -13,13 +13,20 @@ const WebSocket = require('ws');
 const BASE_URL = 'http://localhost:8080';
 const WS_URL = 'ws://localhost:8080';
 
+// Token from /auth/token; required unless the service runs with AUTH_ENABLED=false
+const TOKEN = process.env.TOKEN || '';
+const HEADERS = {
+  'Content-Type': 'application/json',
+  ...(TOKEN && { Authorization: `Bearer ${TOKEN}` })
+};
+
 // Example 1: Get DAG Tips
 async function getDAGTips() {
   console.log('\n📊 Getting DAG tips...');
   
   const response = await fetch(`${BASE_URL}/rpc/getDAGTips`, {
     method: 'POST',
-    headers: { 'Content-Type': 'application/json' },
+    headers: HEADERS,
     body: JSON.stringify({})
   });
   
@@ -42,7 +49,7 @@ async function getBlock(hash) {
   
   const response = await fetch(`${BASE_URL}/rpc/getBlock`, {
     method: 'POST',
-    headers: { 'Content-Type': 'application/json' },
+    headers: HEADERS,
     body: JSON.stringify({
       hash: hash,
       includeTransactions: true
@@ -69,7 +76,7 @@ async function submitTransaction(transaction) {
   
   const response = await fetch(`${BASE_URL}/rpc/submitTransaction`, {
     method: 'POST',
-    headers: { 'Content-Type': 'application/json' },
+    headers: HEADERS,
     body: JSON.stringify({
       transaction: transaction,
       allowOrphan: false
@@ -93,7 +100,7 @@ function subscribeUTXO(addresses) {
   console.log(`📍 Addresses: ${addresses.join(', ')}`);
   
   const addressParams = addresses.join(',');
-  const ws = new WebSocket(`${WS_URL}/ws/subscribeUTXO?addresses=${addressParams}`);
+  const ws = new WebSocket(`${WS_URL}/ws/subscribeUTXO?addresses=${addressParams}&token=${TOKEN}`);
   
   ws.on('open', () => {
     console.log('✅ WebSocket connected');
@@ -113,7 +120,7 @@ function subscribeUTXO(addresses) {
       if (message.added.length > 0) {
         const utxo = message.added[0];
         console.log(`  📍 Address: ${utxo.address}`);
-        console.log(`  💰 Amount: ${utxo.utxo_entry?.amount || 'unknown'}`);
+        console.log(`  💰 Amount: ${utxo.utxoEntry?.amount || 'unknown'}`);
       }
     }
   });
@@ -8,6 +8,7 @@ Requirements: pip install requests websocket-client
 """
 
 import json
+import os
 import time
 import requests
 from websocket import create_connection
@@ -15,6 +16,12 @@ from websocket import create_connection
 BASE_URL = "http://localhost:8080"
 WS_URL = "ws://localhost:8080"
 
+# Token from /auth/token; required unless the service runs with AUTH_ENABLED=false
+TOKEN = os.environ.get("TOKEN", "")
+HEADERS = {"Content-Type": "application/json"}
+if TOKEN:
+    HEADERS["Authorization"] = f"Bearer {TOKEN}"
+
 
 def health_check():
     """Check service health"""
@@ -36,7 +43,7 @@ def get_dag_tips():
     
     response = requests.post(
         f"{BASE_URL}/rpc/getDAGTips",
-        headers={"Content-Type": "application/json"},
+        headers=HEADERS,
         json={}
     )
     
@@ -60,7 +67,7 @@ def get_block(block_hash):
     
     response = requests.post(
         f"{BASE_URL}/rpc/getBlock",
-        headers={"Content-Type": "application/json"},
+        headers=HEADERS,
         json={
             "hash": block_hash,
             "includeTransactions": True
@@ -87,7 +94,7 @@ def submit_transaction(transaction):
     
     response = requests.post(
         f"{BASE_URL}/rpc/submitTransaction",
-        headers={"Content-Type": "application/json"},
+        headers=HEADERS,
         json={
             "transaction": transaction,
             "allowOrphan": False
@@ -111,7 +118,7 @@ def subscribe_utxo(addresses, duration=60):
     print(f"📍 Addresses: {', '.join(addresses)}")
     
     address_params = ",".join(addresses)
-    ws_url = f"{WS_URL}/ws/subscribeUTXO?addresses={address_params}"
+    ws_url = f"{WS_URL}/ws/subscribeUTXO?addresses={address_params}&token={TOKEN}"
     
     try:
         ws = create_connection(ws_url)
@@ -138,8 +145,8 @@ def subscribe_utxo(addresses, duration=60):
                     if data["added"]:
                         utxo = data["added"][0]
                         print(f"  📍 Address: {utxo['address']}")
-                        if utxo.get("utxo_entry"):
-                            print(f"  💰 Amount: {utxo['utxo_entry']['amount']}")
+                        if utxo.get("utxoEntry"):
+                            print(f"  💰 Amount: {utxo['utxoEntry']['amount']}")
                 
             except Exception as e:
                 if "timed out" not in str(e):
@@ -0,0 +1,571 @@
+use crate::error::{FieldError, RpcError};
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Characters of the bech32 alphabet, indexed by their 5-bit value
+const CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+/// Checksum length in 5-bit characters
+const CHECKSUM_LEN: usize = 8;
+
+// Script opcodes of the standard script classes
+const OP_DATA_32: u8 = 0x20;
+const OP_DATA_33: u8 = 0x21;
+const OP_EQUAL: u8 = 0x87;
+const OP_BLAKE2B: u8 = 0xaa;
+const OP_CHECKSIG_ECDSA: u8 = 0xab;
+const OP_CHECKSIG: u8 = 0xac;
+
+/// Network the service runs on, set from `KASPA_NETWORK` at startup
+static NETWORK: OnceLock<Prefix> = OnceLock::new();
+
+/// Human-readable part of an address, one per network
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prefix {
+    Mainnet,
+    Testnet,
+    Simnet,
+    Devnet,
+}
+
+impl Prefix {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Prefix::Mainnet => "kaspa",
+            Prefix::Testnet => "kaspatest",
+            Prefix::Simnet => "kaspasim",
+            Prefix::Devnet => "kaspadev",
+        }
+    }
+
+    /// Network type, as the node names it without its suffix
+    pub fn network_name(self) -> &'static str {
+        match self {
+            Prefix::Mainnet => "mainnet",
+            Prefix::Testnet => "testnet",
+            Prefix::Simnet => "simnet",
+            Prefix::Devnet => "devnet",
+        }
+    }
+
+    /// Prefix for a network ID as the node reports it, e.g. `testnet-10`
+    ///
+    /// Testnets are only told apart by their numeric suffix, so it is
+    /// required there and refused elsewhere.
+    pub fn from_network(network: &str) -> Result<Self, String> {
+        let (name, suffix) = match network.split_once('-') {
+            Some((name, suffix)) => (name, Some(suffix)),
+            None => (network, None),
+        };
+        let prefix = Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.network_name() == name)
+            .ok_or_else(|| format!("Unknown network '{}'", network))?;
+
+        let numeric =
+            |suffix: &str| !suffix.is_empty() && suffix.bytes().all(|b| b.is_ascii_digit());
+        match (prefix, suffix) {
+            (Prefix::Testnet, Some(suffix)) if numeric(suffix) => Ok(prefix),
+            (Prefix::Testnet, _) => Err(format!(
+                "Network '{}' needs a testnet suffix, e.g. 'testnet-10'",
+                network
+            )),
+            (_, None) => Ok(prefix),
+            (_, Some(_)) => Err(format!("Unknown network '{}'", network)),
+        }
+    }
+
+    const ALL: [Prefix; 4] = [
+        Prefix::Mainnet,
+        Prefix::Testnet,
+        Prefix::Simnet,
+        Prefix::Devnet,
+    ];
+
+    fn parse(prefix: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|candidate| candidate.as_str() == prefix)
+    }
+}
+
+/// Set the network addresses are checked against, once at startup
+pub fn set_network(network: &str) -> Result<(), String> {
+    let prefix = Prefix::from_network(network)?;
+    NETWORK
+        .set(prefix)
+        .map_err(|_| "The network is already set".to_string())
+}
+
+/// Network addresses are checked against, once set
+pub fn network() -> Option<Prefix> {
+    NETWORK.get().copied()
+}
+
+/// Kind of key or script an address pays to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Version {
+    /// Schnorr public key
+    PubKey,
+    /// ECDSA public key
+    PubKeyEcdsa,
+    /// Hash of a redeem script
+    ScriptHash,
+}
+
+impl Version {
+    fn byte(self) -> u8 {
+        match self {
+            Version::PubKey => 0,
+            Version::PubKeyEcdsa => 1,
+            Version::ScriptHash => 8,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(Version::PubKey),
+            1 => Some(Version::PubKeyEcdsa),
+            8 => Some(Version::ScriptHash),
+            _ => None,
+        }
+    }
+
+    fn payload_len(self) -> usize {
+        match self {
+            Version::PubKey | Version::ScriptHash => 32,
+            Version::PubKeyEcdsa => 33,
+        }
+    }
+}
+
+/// Standard class of a script public key, named as the node names it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptType {
+    NonStandard,
+    PubKey,
+    PubKeyEcdsa,
+    ScriptHash,
+}
+
+impl ScriptType {
+    /// Classify a script public key, returning the address payload of
+    /// standard scripts
+    pub fn classify(version: u32, script: &[u8]) -> (Self, Option<(Version, &[u8])>) {
+        // Only version 0 scripts have standard forms
+        if version != 0 {
+            return (ScriptType::NonStandard, None);
+        }
+        match script {
+            [OP_DATA_32, key @ .., OP_CHECKSIG] if key.len() == 32 => {
+                (ScriptType::PubKey, Some((Version::PubKey, key)))
+            }
+            [OP_DATA_33, key @ .., OP_CHECKSIG_ECDSA] if key.len() == 33 => {
+                (ScriptType::PubKeyEcdsa, Some((Version::PubKeyEcdsa, key)))
+            }
+            [OP_BLAKE2B, OP_DATA_32, hash @ .., OP_EQUAL] if hash.len() == 32 => {
+                (ScriptType::ScriptHash, Some((Version::ScriptHash, hash)))
+            }
+            _ => (ScriptType::NonStandard, None),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ScriptType::NonStandard => "nonstandard",
+            ScriptType::PubKey => "pubkey",
+            ScriptType::PubKeyEcdsa => "pubkeyecdsa",
+            ScriptType::ScriptHash => "scripthash",
+        }
+    }
+}
+
+/// A decoded `kaspa:`/`kaspatest:` address
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    pub prefix: Prefix,
+    pub version: Version,
+    pub payload: Vec<u8>,
+}
+
+impl Address {
+    /// Decode an address and verify its checksum
+    pub fn parse(address: &str) -> Result<Self, String> {
+        let (prefix, data) = address
+            .split_once(':')
+            .ok_or_else(|| "Missing network prefix, e.g. 'kaspa:'".to_string())?;
+        let prefix =
+            Prefix::parse(prefix).ok_or_else(|| format!("Unknown network prefix '{}'", prefix))?;
+
+        let values = data
+            .bytes()
+            .map(|c| {
+                CHARSET
+                    .iter()
+                    .position(|&v| v == c)
+                    .map(|v| v as u8)
+                    .ok_or_else(|| format!("Invalid character '{}'", c as char))
+            })
+            .collect::<Result<Vec<u8>, String>>()?;
+        if values.len() <= CHECKSUM_LEN {
+            return Err("Address is too short".into());
+        }
+
+        let (payload, checksum) = values.split_at(values.len() - CHECKSUM_LEN);
+        let expected = checksum.iter().fold(0u64, |acc, &v| (acc << 5) | v as u64);
+        if self::checksum(payload, prefix) != expected {
+            return Err("Invalid checksum".into());
+        }
+
+        let bytes =
+            convert_bits(payload, 5, 8, false).ok_or_else(|| "Invalid padding".to_string())?;
+        let (&version, payload) = bytes
+            .split_first()
+            .ok_or_else(|| "Missing address version".to_string())?;
+        let version = Version::from_byte(version)
+            .ok_or_else(|| format!("Unknown address version {}", version))?;
+        if payload.len() != version.payload_len() {
+            return Err(format!(
+                "Expected a {}-byte payload, got {}",
+                version.payload_len(),
+                payload.len()
+            ));
+        }
+
+        Ok(Self {
+            prefix,
+            version,
+            payload: payload.to_vec(),
+        })
+    }
+
+    /// Address a standard script public key pays to
+    pub fn from_script(version: u32, script: &[u8], prefix: Prefix) -> Option<Self> {
+        let (_, payload) = ScriptType::classify(version, script);
+        payload.map(|(version, payload)| Self {
+            prefix,
+            version,
+            payload: payload.to_vec(),
+        })
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut bytes = Vec::with_capacity(1 + self.payload.len());
+        bytes.push(self.version.byte());
+        bytes.extend_from_slice(&self.payload);
+
+        let payload = convert_bits(&bytes, 8, 5, true).unwrap_or_default();
+        let checksum = checksum(&payload, self.prefix);
+        let checksum = (0..CHECKSUM_LEN)
+            .rev()
+            .map(|i| ((checksum >> (5 * i)) & 0x1f) as u8);
+
+        let data: String = payload
+            .iter()
+            .copied()
+            .chain(checksum)
+            .map(|v| CHARSET[v as usize] as char)
+            .collect();
+        write!(f, "{}:{}", self.prefix.as_str(), data)
+    }
+}
+
+/// Check that `address` decodes and belongs to the node's network
+pub fn check(address: &str) -> Result<Address, String> {
+    let parsed = Address::parse(address)?;
+    match network() {
+        Some(network) if network != parsed.prefix => Err(format!(
+            "Address is for '{}' but the node runs on '{}'",
+            parsed.prefix.as_str(),
+            network.as_str()
+        )),
+        _ => Ok(parsed),
+    }
+}
+
+/// Check every address of a request, reporting each invalid one as `field[i]`
+pub fn validate(field: &str, addresses: &[String]) -> Result<(), RpcError> {
+    let errors: Vec<_> = addresses
+        .iter()
+        .enumerate()
+        .filter_map(|(i, address)| {
+            check(address).err().map(|message| FieldError {
+                field: format!("{}[{}]", field, i),
+                message,
+            })
+        })
+        .collect();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(RpcError::Validation(errors))
+    }
+}
+
+/// Check a single address of a request
+pub fn validate_one(field: &str, address: &str) -> Result<(), RpcError> {
+    check(address).map(|_| ()).map_err(|message| {
+        RpcError::Validation(vec![FieldError {
+            field: field.to_string(),
+            message,
+        }])
+    })
+}
+
+/// Checksum over the prefix and 5-bit payload, as a 40-bit number
+fn checksum(payload: &[u8], prefix: Prefix) -> u64 {
+    let prefix = prefix.as_str().bytes().map(|c| c & 0x1f);
+    polymod(
+        prefix
+            .chain([0])
+            .chain(payload.iter().copied())
+            .chain([0; CHECKSUM_LEN]),
+    )
+}
+
+fn polymod(values: impl Iterator<Item = u8>) -> u64 {
+    const GENERATORS: [u64; 5] = [
+        0x98f2bc8e61,
+        0x79b76d99e2,
+        0xf33e5fb3c4,
+        0xae2eabe2a8,
+        0x1e4f43e470,
+    ];
+
+    let mut c = 1u64;
+    for value in values {
+        let top = c >> 35;
+        c = ((c & 0x07ffffffff) << 5) ^ value as u64;
+        for (i, generator) in GENERATORS.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                c ^= generator;
+            }
+        }
+    }
+    c ^ 1
+}
+
+/// Regroup bits, e.g. bytes into 5-bit values; `None` on leftover bits
+/// that `pad` does not allow
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let mut acc = 0u32;
+    let mut bits = 0u32;
+    let max = (1u32 << to) - 1;
+    // Only the bits not yet emitted are kept
+    let max_acc = (1u32 << (from + to - 1)) - 1;
+    let mut out = Vec::with_capacity(data.len() * from as usize / to as usize + 1);
+
+    for &value in data {
+        acc = ((acc << from) | value as u32) & max_acc;
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & max) as u8);
+        }
+    }
+
+    if pad {
+        if bits > 0 {
+            out.push(((acc << (to - bits)) & max) as u8);
+        }
+    } else if bits >= from || (acc << (to - bits)) & max != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Known addresses with their payloads
+    const MAINNET_PUBKEY: &str =
+        "kaspa:qqkqkzjvr7zwxxmjxjkmxxdwju9kjs6e9u82uh59z07vgaks6gg62v8707g73";
+    const MAINNET_PUBKEY_PAYLOAD: &str =
+        "2c0b0a4c1f84e31b7234adb319ae970b6943592f0eae5e8513fcc476d0d211a5";
+    const MAINNET_PUBKEY_ECDSA: &str =
+        "kaspa:qyp0r5mcq4rd5grj3652ra09u5dcgwqq9ntuswp247nama5quyj40eq03sc2dkx";
+    const MAINNET_PUBKEY_ECDSA_PAYLOAD: &str =
+        "02f1d3780546da20728ea8a1f5e5e51b8438002cd7c8382aafa7ddf680e12557e4";
+    const MAINNET_SCRIPT_HASH: &str =
+        "kaspa:precqv0krj3r6uyyfa36ga7s0u9jct0v4wg8ctsfde2gkrsgwgw8jgxfzfc98";
+    const MAINNET_SCRIPT_HASH_PAYLOAD: &str =
+        "f38031f61ca23d70844f63a477d07f0b2c2decab907c2e096e548b0e08721c79";
+    const TESTNET_PUBKEY: &str =
+        "kaspatest:qqnapngv3zxp305qf06w6hpzmyxtx2r99jjhs04lu980xdyd2ulwwmx9evrfz";
+    const TESTNET_PUBKEY_PAYLOAD: &str =
+        "27d0cd0c888c18be804bf4ed5c22d90cb328652ca5783ebfe14ef3348d573ee7";
+
+    fn address(prefix: Prefix, version: Version, payload: &str) -> Address {
+        Address {
+            prefix,
+            version,
+            payload: hex::decode(payload).unwrap(),
+        }
+    }
+
+    #[test]
+    fn parses_known_addresses() {
+        let cases = [
+            (
+                MAINNET_PUBKEY,
+                Prefix::Mainnet,
+                Version::PubKey,
+                MAINNET_PUBKEY_PAYLOAD,
+            ),
+            (
+                MAINNET_PUBKEY_ECDSA,
+                Prefix::Mainnet,
+                Version::PubKeyEcdsa,
+                MAINNET_PUBKEY_ECDSA_PAYLOAD,
+            ),
+            (
+                MAINNET_SCRIPT_HASH,
+                Prefix::Mainnet,
+                Version::ScriptHash,
+                MAINNET_SCRIPT_HASH_PAYLOAD,
+            ),
+            (
+                TESTNET_PUBKEY,
+                Prefix::Testnet,
+                Version::PubKey,
+                TESTNET_PUBKEY_PAYLOAD,
+            ),
+        ];
+        for (encoded, prefix, version, payload) in cases {
+            let parsed = Address::parse(encoded).unwrap();
+            assert_eq!(parsed, address(prefix, version, payload), "{}", encoded);
+            assert_eq!(parsed.to_string(), encoded);
+        }
+    }
+
+    #[test]
+    fn testnet_round_trips_every_version() {
+        for (version, payload) in [
+            (Version::PubKeyEcdsa, MAINNET_PUBKEY_ECDSA_PAYLOAD),
+            (Version::ScriptHash, MAINNET_SCRIPT_HASH_PAYLOAD),
+        ] {
+            let testnet = address(Prefix::Testnet, version, payload);
+            let encoded = testnet.to_string();
+            assert!(encoded.starts_with("kaspatest:"));
+            assert_eq!(Address::parse(&encoded).unwrap(), testnet);
+        }
+    }
+
+    #[test]
+    fn checksum_covers_prefix_and_payload() {
+        // An empty version 0 payload under prefix `a` encodes as `a:qqeq69uvrh`
+        let values = [1, 0, 0, 0].into_iter().chain([0; CHECKSUM_LEN]);
+        let expected = b"eq69uvrh".iter().fold(0u64, |acc, c| {
+            (acc << 5) | CHARSET.iter().position(|v| v == c).unwrap() as u64
+        });
+        assert_eq!(polymod(values), expected);
+
+        let (_, body) = MAINNET_PUBKEY.split_once(':').unwrap();
+        assert_eq!(
+            Address::parse(&format!("kaspatest:{}", body)),
+            Err("Invalid checksum".to_string())
+        );
+
+        let mut typo = MAINNET_PUBKEY.to_string();
+        typo.replace_range(10..11, "p");
+        assert_ne!(typo, MAINNET_PUBKEY);
+        assert_eq!(Address::parse(&typo), Err("Invalid checksum".to_string()));
+    }
+
+    #[test]
+    fn rejects_malformed_addresses() {
+        assert!(
+            Address::parse("qqkqkzjvr7zwxxmjxjkmxxdwju9kjs6e9u82uh59z07vgaks6gg62v8707g73")
+                .is_err()
+        );
+        assert!(Address::parse(
+            "bitcoin:qqkqkzjvr7zwxxmjxjkmxxdwju9kjs6e9u82uh59z07vgaks6gg62v8707g73"
+        )
+        .is_err());
+        assert!(Address::parse(&MAINNET_PUBKEY.to_uppercase()).is_err());
+        assert!(Address::parse("kaspa:qqeq69uv").is_err());
+    }
+
+    #[test]
+    fn convert_bits_regroups_and_checks_padding() {
+        assert_eq!(convert_bits(&[0xff], 8, 5, true), Some(vec![31, 28]));
+        assert_eq!(convert_bits(&[31, 28], 5, 8, false), Some(vec![0xff]));
+        // Leftover bits must be zero padding
+        assert_eq!(convert_bits(&[31, 29], 5, 8, false), None);
+
+        let bytes: Vec<u8> = (0..=255).collect();
+        let values = convert_bits(&bytes, 8, 5, true).unwrap();
+        assert!(values.iter().all(|&v| v < 32));
+        assert_eq!(convert_bits(&values, 5, 8, false), Some(bytes));
+    }
+
+    #[test]
+    fn classifies_standard_scripts() {
+        let payload = hex::decode(MAINNET_PUBKEY_PAYLOAD).unwrap();
+        let mut script = vec![OP_DATA_32];
+        script.extend_from_slice(&payload);
+        script.push(OP_CHECKSIG);
+        assert_eq!(ScriptType::classify(0, &script).0, ScriptType::PubKey);
+        assert_eq!(
+            Address::from_script(0, &script, Prefix::Mainnet)
+                .unwrap()
+                .to_string(),
+            MAINNET_PUBKEY
+        );
+
+        let key = hex::decode(MAINNET_PUBKEY_ECDSA_PAYLOAD).unwrap();
+        let mut script = vec![OP_DATA_33];
+        script.extend_from_slice(&key);
+        script.push(OP_CHECKSIG_ECDSA);
+        assert_eq!(ScriptType::classify(0, &script).0, ScriptType::PubKeyEcdsa);
+        assert_eq!(
+            Address::from_script(0, &script, Prefix::Mainnet)
+                .unwrap()
+                .to_string(),
+            MAINNET_PUBKEY_ECDSA
+        );
+
+        let hash = hex::decode(MAINNET_SCRIPT_HASH_PAYLOAD).unwrap();
+        let mut script = vec![OP_BLAKE2B, OP_DATA_32];
+        script.extend_from_slice(&hash);
+        script.push(OP_EQUAL);
+        assert_eq!(ScriptType::classify(0, &script).0, ScriptType::ScriptHash);
+        assert_eq!(
+            Address::from_script(0, &script, Prefix::Mainnet)
+                .unwrap()
+                .to_string(),
+            MAINNET_SCRIPT_HASH
+        );
+
+        // Newer script versions and unknown shapes have no address
+        assert_eq!(
+            ScriptType::classify(1, &script),
+            (ScriptType::NonStandard, None)
+        );
+        assert_eq!(
+            ScriptType::classify(0, &script[..script.len() - 1]),
+            (ScriptType::NonStandard, None)
+        );
+    }
+
+    #[test]
+    fn prefixes_follow_node_network_names() {
+        assert_eq!(Prefix::from_network("mainnet"), Ok(Prefix::Mainnet));
+        assert_eq!(Prefix::from_network("testnet-10"), Ok(Prefix::Testnet));
+        assert_eq!(Prefix::from_network("testnet-11"), Ok(Prefix::Testnet));
+        assert_eq!(Prefix::from_network("simnet"), Ok(Prefix::Simnet));
+        assert!(Prefix::from_network("kaspa-mainnet").is_err());
+    }
+
+    #[test]
+    fn testnets_need_their_suffix() {
+        assert!(Prefix::from_network("testnet").is_err());
+        assert!(Prefix::from_network("testnet-").is_err());
+        assert!(Prefix::from_network("testnet-ten").is_err());
+        assert!(Prefix::from_network("mainnet-1").is_err());
+    }
+}
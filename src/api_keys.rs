@@ -0,0 +1,196 @@
+use crate::error::RpcError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::RwLock;
+
+/// Stored API key; only the bcrypt hash of the secret is kept
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyRecord {
+    pub id: String,
+    pub name: String,
+    pub role: String,
+    pub key_hash: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// File-backed store of bcrypt-hashed API keys
+///
+/// Keys have the form `<id>.<secret>`, so a presented key is checked
+/// against a single hash. The whole store is rewritten on every change;
+/// it holds credentials for a handful of integrations, not end users.
+pub struct ApiKeyStore {
+    path: PathBuf,
+    keys: RwLock<HashMap<String, ApiKeyRecord>>,
+    // Hash of the ADMIN_API_KEY bootstrap credential, if configured
+    bootstrap_hash: Option<String>,
+    // Serializes writes so concurrent changes cannot interleave on disk
+    persist_lock: tokio::sync::Mutex<()>,
+}
+
+impl ApiKeyStore {
+    /// Load keys from `path`, starting empty if the file does not exist yet
+    pub fn load(path: impl Into<PathBuf>, bootstrap_key: Option<&str>) -> Result<Self, RpcError> {
+        let path = path.into();
+
+        let records: Vec<ApiKeyRecord> = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data).map_err(|e| {
+                RpcError::Internal(format!("Invalid API key file {}: {}", path.display(), e))
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => {
+                return Err(RpcError::Internal(format!(
+                    "Failed to read API key file {}: {}",
+                    path.display(),
+                    e
+                )))
+            }
+        };
+
+        let bootstrap_hash = bootstrap_key
+            .map(|key| bcrypt::hash(key, bcrypt::DEFAULT_COST))
+            .transpose()
+            .map_err(|e| RpcError::Internal(format!("Failed to hash admin key: {}", e)))?;
+
+        tracing::info!("Loaded {} API keys from {}", records.len(), path.display());
+
+        Ok(Self {
+            path,
+            keys: RwLock::new(
+                records
+                    .into_iter()
+                    .map(|record| (record.id.clone(), record))
+                    .collect(),
+            ),
+            bootstrap_hash,
+            persist_lock: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Create a key, returning its record and the plaintext key (shown once)
+    pub async fn create(
+        &self,
+        name: String,
+        role: String,
+    ) -> Result<(ApiKeyRecord, String), RpcError> {
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let secret = format!(
+            "{}{}",
+            uuid::Uuid::new_v4().simple(),
+            uuid::Uuid::new_v4().simple()
+        );
+
+        let key_hash = hash_secret(secret.clone()).await?;
+        let record = ApiKeyRecord {
+            id: id.clone(),
+            name,
+            role,
+            key_hash,
+            created_at: chrono::Utc::now(),
+            revoked_at: None,
+        };
+
+        self.keys
+            .write()
+            .unwrap()
+            .insert(id.clone(), record.clone());
+        self.persist().await?;
+
+        Ok((record, format!("{}.{}", id, secret)))
+    }
+
+    /// All keys, oldest first
+    pub fn list(&self) -> Vec<ApiKeyRecord> {
+        let mut records: Vec<_> = self.keys.read().unwrap().values().cloned().collect();
+        records.sort_by_key(|record| record.created_at);
+        records
+    }
+
+    /// Revoke a key; tokens issued for it stop being accepted
+    pub async fn revoke(&self, id: &str) -> Result<ApiKeyRecord, RpcError> {
+        let record = {
+            let mut keys = self.keys.write().unwrap();
+            let record = keys
+                .get_mut(id)
+                .ok_or_else(|| RpcError::NotFound(format!("API key {} not found", id)))?;
+            record.revoked_at.get_or_insert_with(chrono::Utc::now);
+            record.clone()
+        };
+
+        self.persist().await?;
+        Ok(record)
+    }
+
+    /// Whether `id` names a revoked key
+    pub fn is_revoked(&self, id: &str) -> bool {
+        self.keys
+            .read()
+            .unwrap()
+            .get(id)
+            .is_some_and(|record| record.revoked_at.is_some())
+    }
+
+    /// Check a presented key, returning the subject and role to issue a token for
+    pub async fn verify(&self, key: &str) -> Result<(String, String), RpcError> {
+        let invalid = || RpcError::Auth("Invalid API key".into());
+
+        let stored = key.split_once('.').and_then(|(id, secret)| {
+            let keys = self.keys.read().unwrap();
+            keys.get(id)
+                .map(|record| (record.clone(), secret.to_string()))
+        });
+
+        if let Some((record, secret)) = stored {
+            if record.revoked_at.is_some() || !verify_secret(secret, record.key_hash).await? {
+                return Err(invalid());
+            }
+            return Ok((record.id, record.role));
+        }
+
+        match &self.bootstrap_hash {
+            Some(hash) if verify_secret(key.to_string(), hash.clone()).await? => {
+                Ok(("admin".to_string(), crate::auth::ADMIN_ROLE.to_string()))
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    /// Write the store to disk, replacing the previous file atomically
+    async fn persist(&self) -> Result<(), RpcError> {
+        let _guard = self.persist_lock.lock().await;
+
+        let data = serde_json::to_vec_pretty(&self.list())
+            .map_err(|e| RpcError::Internal(format!("Failed to encode API keys: {}", e)))?;
+
+        let write_error = |e: std::io::Error| {
+            RpcError::Internal(format!(
+                "Failed to write API key file {}: {}",
+                self.path.display(),
+                e
+            ))
+        };
+
+        let tmp = self.path.with_extension("tmp");
+        tokio::fs::write(&tmp, data).await.map_err(write_error)?;
+        tokio::fs::rename(&tmp, &self.path)
+            .await
+            .map_err(write_error)
+    }
+}
+
+/// bcrypt is deliberately slow, so keep it off the async workers
+async fn hash_secret(secret: String) -> Result<String, RpcError> {
+    tokio::task::spawn_blocking(move || bcrypt::hash(secret, bcrypt::DEFAULT_COST))
+        .await
+        .map_err(|e| RpcError::Internal(e.to_string()))?
+        .map_err(|e| RpcError::Internal(format!("Failed to hash API key: {}", e)))
+}
+
+async fn verify_secret(secret: String, hash: String) -> Result<bool, RpcError> {
+    tokio::task::spawn_blocking(move || bcrypt::verify(secret, &hash))
+        .await
+        .map_err(|e| RpcError::Internal(e.to_string()))?
+        .map_err(|e| RpcError::Internal(format!("Failed to verify API key: {}", e)))
+}
@@ -1,8 +1,29 @@
-use crate::error::RpcError;
+use crate::{error::RpcError, AppState};
+use axum::{
+    extract::{Query, Request, State},
+    http::{header, Method},
+    middleware::Next,
+    response::Response,
+};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Role allowed through every role check
+pub const ADMIN_ROLE: &str = "admin";
+
+/// Role needed to broadcast transactions
+pub const SUBMITTER_ROLE: &str = "submitter";
+
+/// Role for read-only access
+pub const USER_ROLE: &str = "user";
+
+/// Roles an API key can be issued with
+pub const ROLES: [&str; 3] = [ADMIN_ROLE, SUBMITTER_ROLE, USER_ROLE];
+
+/// How long an issued token stays valid
+pub const TOKEN_LIFETIME_SECS: usize = 3600 * 24;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String, // Subject (user ID)
     pub exp: usize,  // Expiry timestamp
@@ -13,7 +34,7 @@ pub struct Claims {
 /// Generate a JWT token
 pub fn generate_token(user_id: &str, secret: &str, role: &str) -> Result<String, RpcError> {
     let now = chrono::Utc::now().timestamp() as usize;
-    let expiry = now + 3600 * 24; // 24 hours
+    let expiry = now + TOKEN_LIFETIME_SECS;
 
     let claims = Claims {
         sub: user_id.to_string(),
@@ -43,15 +64,75 @@ pub fn validate_token(token: &str, secret: &str) -> Result<Claims, RpcError> {
     .map_err(|e| RpcError::Auth(format!("Invalid token: {}", e)))
 }
 
-/// Middleware skeleton for JWT authentication
-/// Usage: Add as layer to protected routes
+#[derive(Debug, Deserialize)]
+struct TokenQuery {
+    token: String,
+}
+
+/// Middleware rejecting requests without a valid JWT
+///
+/// The token is read from the `Authorization: Bearer` header. Browsers
+/// cannot set headers on WebSocket or EventSource requests, so `GET`
+/// requests may pass it as a `token` query parameter instead. The
+/// validated `Claims` are inserted into the request extensions. Tokens
+/// issued for a since-revoked API key are rejected.
 pub async fn require_auth(
-    // Extract Authorization header
-    // Validate JWT
-    // Pass through if valid, return 401 if not
-) -> Result<(), RpcError> {
-    // Implementation placeholder
-    // In production, this would extract the Bearer token from headers
-    // and validate it using validate_token()
-    Ok(())
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, RpcError> {
+    let token = bearer_token(&request).or_else(|| {
+        if request.method() != Method::GET {
+            return None;
+        }
+        Query::<TokenQuery>::try_from_uri(request.uri())
+            .ok()
+            .map(|Query(query)| query.token)
+    });
+
+    let Some(token) = token else {
+        return Err(RpcError::Auth("Missing bearer token".into()));
+    };
+
+    let claims = validate_token(&token, &state.jwt_secret)?;
+    if state.api_keys.is_revoked(&claims.sub) {
+        return Err(RpcError::Auth("API key revoked".into()));
+    }
+    request.extensions_mut().insert(claims);
+
+    Ok(next.run(request).await)
+}
+
+/// Middleware rejecting callers whose token lacks `role`
+///
+/// Admins pass every check. Callers without a token are only trusted
+/// when authentication was explicitly disabled.
+pub async fn require_role(
+    role: &'static str,
+    auth_enabled: bool,
+    request: Request,
+    next: Next,
+) -> Result<Response, RpcError> {
+    match request.extensions().get::<Claims>() {
+        Some(claims) if claims.role != role && claims.role != ADMIN_ROLE => {
+            return Err(RpcError::Forbidden(format!("Requires role '{}'", role)));
+        }
+        None if auth_enabled => {
+            return Err(RpcError::Auth("Missing bearer token".into()));
+        }
+        _ => {}
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// Token from an `Authorization: Bearer` header, if present
+fn bearer_token(request: &Request) -> Option<String> {
+    request
+        .headers()
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(|token| token.trim().to_string())
 }
@@ -0,0 +1,154 @@
+use crate::{client::proto::kaspad_response::Payload, metrics, upstream::UpstreamPool};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::error::RecvError;
+
+/// Sompi per KAS
+pub const SOMPI_PER_KAS: u64 = 100_000_000;
+
+/// Format a sompi amount as an exact decimal KAS string
+pub fn sompi_to_kas(sompi: u64) -> String {
+    format!("{}.{:08}", sompi / SOMPI_PER_KAS, sompi % SOMPI_PER_KAS)
+}
+
+struct Cached {
+    balance: u64,
+    fetched_at: Instant,
+}
+
+#[derive(Default)]
+struct Entries {
+    balances: HashMap<String, Cached>,
+    // When each address last saw a UTXO change, to drop fetches it overtook
+    invalidated: HashMap<String, Instant>,
+    // Last time notifications were missed and every entry was dropped
+    cleared_at: Option<Instant>,
+}
+
+/// Short-lived cache of address balances
+///
+/// Entries expire after `ttl`, which is all that keeps the balance of a
+/// merely polled address fresh: it may lag its UTXOs by up to `ttl`.
+/// Addresses with UTXO notifications registered upstream (watched over
+/// WebSocket) are also dropped as soon as the node reports a change.
+/// Polled addresses are not registered themselves, as every cached
+/// address would then cost a registration round trip on each expiry.
+pub struct BalanceCache {
+    ttl: Duration,
+    entries: Mutex<Entries>,
+}
+
+impl BalanceCache {
+    pub fn new(upstreams: &UpstreamPool, ttl: Duration) -> Arc<Self> {
+        let cache = Arc::new(Self {
+            ttl,
+            entries: Mutex::new(Entries::default()),
+        });
+
+        // Listen on every node: notifications come from whichever one
+        // currently serves the shared UTXO registration
+        for upstream in upstreams.upstreams() {
+            let mut notifications = upstream.client().notifications();
+            let cache = Arc::downgrade(&cache);
+            tokio::spawn(async move {
+                loop {
+                    let notification = match notifications.recv().await {
+                        Ok(notification) => notification,
+                        Err(RecvError::Lagged(_)) => {
+                            // Missed changes could leave anything stale
+                            match cache.upgrade() {
+                                Some(cache) => cache.clear(),
+                                None => break,
+                            }
+                            continue;
+                        }
+                        Err(RecvError::Closed) => break,
+                    };
+                    let Some(cache) = cache.upgrade() else {
+                        break;
+                    };
+                    if let Payload::UtxosChangedNotification(changed) = notification.as_ref() {
+                        let addresses = changed.added.iter().chain(&changed.removed);
+                        cache.invalidate(addresses.map(|entry| entry.address.as_str()));
+                    }
+                }
+            });
+        }
+
+        let sweeper = Arc::downgrade(&cache);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(ttl.max(Duration::from_secs(1)));
+            loop {
+                ticker.tick().await;
+                let Some(cache) = sweeper.upgrade() else {
+                    break;
+                };
+                cache.sweep();
+            }
+        });
+
+        cache
+    }
+
+    /// Cached balance of `address`, if still fresh
+    pub fn get(&self, address: &str) -> Option<u64> {
+        let entries = self.entries.lock().unwrap();
+        let balance = entries
+            .balances
+            .get(address)
+            .filter(|cached| cached.fetched_at.elapsed() < self.ttl)
+            .map(|cached| cached.balance);
+
+        metrics::record_cache_lookup("balance", balance.is_some());
+        balance
+    }
+
+    /// Store a balance fetched from the node, unless a UTXO change for
+    /// the address arrived after the fetch started
+    pub fn insert(&self, address: String, balance: u64, fetch_started: Instant) {
+        let mut entries = self.entries.lock().unwrap();
+        let overtaken = |changed_at: &Instant| *changed_at >= fetch_started;
+        if entries.invalidated.get(&address).is_some_and(overtaken)
+            || entries.cleared_at.as_ref().is_some_and(overtaken)
+        {
+            return;
+        }
+        entries.balances.insert(
+            address,
+            Cached {
+                balance,
+                fetched_at: fetch_started,
+            },
+        );
+    }
+
+    fn invalidate<'a>(&self, addresses: impl Iterator<Item = &'a str>) {
+        let now = Instant::now();
+        let mut entries = self.entries.lock().unwrap();
+        for address in addresses {
+            entries.balances.remove(address);
+            entries.invalidated.insert(address.to_string(), now);
+        }
+    }
+
+    /// Drop every entry, for when UTXO changes may have been missed
+    pub fn clear(&self) {
+        let mut entries = self.entries.lock().unwrap();
+        entries.balances.clear();
+        // Fetches still in flight may predate the missed changes
+        entries.cleared_at = Some(Instant::now());
+    }
+
+    fn sweep(&self) {
+        let ttl = self.ttl;
+        let mut entries = self.entries.lock().unwrap();
+        entries
+            .balances
+            .retain(|_, cached| cached.fetched_at.elapsed() < ttl);
+        // Fetches older than the TTL are never cached anyway
+        entries
+            .invalidated
+            .retain(|_, changed_at| changed_at.elapsed() < ttl);
+    }
+}
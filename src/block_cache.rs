@@ -0,0 +1,183 @@
+use crate::{client::proto::RpcBlock, error::RpcError, metrics};
+use prost::Message;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::OnceCell;
+
+/// How long a block without children is served from cache, since its
+/// children and chain status are still being decided
+const TIP_BLOCK_TTL: Duration = Duration::from_secs(1);
+
+/// Block hash and whether transactions were included
+type Key = (String, bool);
+
+type Fetch = Arc<OnceCell<Result<Arc<RpcBlock>, RpcError>>>;
+
+struct Cached {
+    block: Arc<RpcBlock>,
+    size: usize,
+    // Position in the recency order
+    last_used: u64,
+    // Verbose data keeps changing as the DAG grows, so every block expires
+    expires_at: Instant,
+}
+
+#[derive(Default)]
+struct Entries {
+    blocks: HashMap<Key, Cached>,
+    // Keys by last use, oldest first
+    recency: BTreeMap<u64, Key>,
+    tick: u64,
+    size: usize,
+}
+
+impl Entries {
+    fn touch(&mut self, key: &Key) -> Option<Arc<RpcBlock>> {
+        let cached = self.blocks.get_mut(key)?;
+        if cached.expires_at <= Instant::now() {
+            self.remove(key);
+            metrics::record_cache_size("block", self.size);
+            return None;
+        }
+
+        self.tick += 1;
+        self.recency.remove(&cached.last_used);
+        cached.last_used = self.tick;
+        self.recency.insert(self.tick, key.clone());
+        Some(cached.block.clone())
+    }
+
+    fn remove(&mut self, key: &Key) {
+        if let Some(cached) = self.blocks.remove(key) {
+            self.recency.remove(&cached.last_used);
+            self.size -= cached.size;
+        }
+    }
+}
+
+/// LRU cache of blocks with request coalescing
+///
+/// Headers and transactions never change for a hash, but verbose data
+/// does: children, chain status and header-only state follow the DAG.
+/// Blocks are therefore served for at most `ttl`, and tip blocks for
+/// `TIP_BLOCK_TTL`, unless the cache exceeds its byte budget first and
+/// evicts the least recently used ones. Sizes are the blocks' encoded
+/// sizes, which undercount the memory actually held. Concurrent
+/// requests for the same block share a single call to the node.
+pub struct BlockCache {
+    max_size: usize,
+    ttl: Duration,
+    entries: Mutex<Entries>,
+    in_flight: Mutex<HashMap<Key, Fetch>>,
+}
+
+impl BlockCache {
+    /// Cache holding up to `max_size` bytes of blocks for up to `ttl`;
+    /// a zero size or TTL only coalesces
+    pub fn new(max_size: usize, ttl: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            max_size,
+            ttl,
+            entries: Mutex::new(Entries::default()),
+            in_flight: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Cached block, or the result of `fetch`, shared with every
+    /// concurrent request for the same block
+    pub async fn get_or_fetch<F, Fut>(
+        &self,
+        hash: &str,
+        include_transactions: bool,
+        fetch: F,
+    ) -> Result<Arc<RpcBlock>, RpcError>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<RpcBlock, RpcError>>,
+    {
+        let key = (hash.to_lowercase(), include_transactions);
+
+        let cached = self.entries.lock().unwrap().touch(&key);
+        metrics::record_cache_lookup("block", cached.is_some());
+        if let Some(block) = cached {
+            return Ok(block);
+        }
+
+        let fetch_cell = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+
+        // Should the fetching request be dropped, a waiting one takes over
+        let result = fetch_cell
+            .get_or_init(|| async {
+                let block = fetch().await.map(Arc::new);
+                if let Ok(block) = &block {
+                    self.insert(key.clone(), block.clone());
+                }
+                block
+            })
+            .await
+            .clone();
+
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if in_flight
+            .get(&key)
+            .is_some_and(|cell| Arc::ptr_eq(cell, &fetch_cell))
+        {
+            in_flight.remove(&key);
+        }
+
+        result
+    }
+
+    fn insert(&self, key: Key, block: Arc<RpcBlock>) {
+        let size = block.encoded_len();
+        if size > self.max_size || self.ttl.is_zero() {
+            return;
+        }
+
+        let settled = block
+            .verbose_data
+            .as_ref()
+            .is_some_and(|v| !v.children_hashes.is_empty() && !v.is_header_only);
+        let ttl = if settled {
+            self.ttl
+        } else {
+            self.ttl.min(TIP_BLOCK_TTL)
+        };
+        let expires_at = Instant::now() + ttl;
+
+        let mut entries = self.entries.lock().unwrap();
+        entries.remove(&key);
+
+        entries.tick += 1;
+        let last_used = entries.tick;
+        entries.recency.insert(last_used, key.clone());
+        entries.size += size;
+        entries.blocks.insert(
+            key,
+            Cached {
+                block,
+                size,
+                last_used,
+                expires_at,
+            },
+        );
+
+        while entries.size > self.max_size {
+            let Some((_, oldest)) = entries.recency.pop_first() else {
+                break;
+            };
+            if let Some(cached) = entries.blocks.remove(&oldest) {
+                entries.size -= cached.size;
+            }
+        }
+        metrics::record_cache_size("block", entries.size);
+    }
+}
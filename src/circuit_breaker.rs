@@ -0,0 +1,104 @@
+use crate::metrics;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Consecutive failed calls that open the circuit
+const FAILURE_THRESHOLD: u32 = 5;
+
+/// How long an open circuit rejects calls before letting a trial through
+const OPEN_DURATION: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    Closed,
+    HalfOpen,
+    Open,
+}
+
+impl CircuitState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CircuitState::Closed => "closed",
+            CircuitState::HalfOpen => "half_open",
+            CircuitState::Open => "open",
+        }
+    }
+}
+
+struct Inner {
+    state: CircuitState,
+    consecutive_failures: u32,
+    // When the next trial call may go through an open circuit
+    retry_at: Instant,
+}
+
+/// Circuit breaker for the calls made to one upstream node
+///
+/// After `FAILURE_THRESHOLD` consecutive timeouts or dropped streams the
+/// circuit opens and calls fail fast, so the pool fails over at once
+/// instead of waiting on a hung node. Every `OPEN_DURATION` a single
+/// trial call is let through; its outcome closes or reopens the circuit.
+pub struct CircuitBreaker {
+    upstream: String,
+    inner: Mutex<Inner>,
+}
+
+impl CircuitBreaker {
+    pub fn new(upstream: &str) -> Self {
+        metrics::record_circuit_state(upstream, CircuitState::Closed);
+        Self {
+            upstream: upstream.to_string(),
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                retry_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Whether a call may go ahead
+    pub fn admit(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let now = Instant::now();
+        match inner.state {
+            CircuitState::Closed => true,
+            // A trial that never reports back is replaced after a while
+            _ if now >= inner.retry_at => {
+                inner.retry_at = now + OPEN_DURATION;
+                self.transition(&mut inner, CircuitState::HalfOpen);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures = 0;
+        self.transition(&mut inner, CircuitState::Closed);
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures += 1;
+        if inner.state == CircuitState::HalfOpen || inner.consecutive_failures >= FAILURE_THRESHOLD
+        {
+            inner.retry_at = Instant::now() + OPEN_DURATION;
+            self.transition(&mut inner, CircuitState::Open);
+        }
+    }
+
+    fn transition(&self, inner: &mut Inner, state: CircuitState) {
+        if inner.state == state {
+            return;
+        }
+        tracing::info!(
+            upstream = %self.upstream,
+            "Circuit {} -> {}",
+            inner.state.as_str(),
+            state.as_str()
+        );
+        inner.state = state;
+        metrics::record_circuit_state(&self.upstream, state);
+    }
+}
@@ -1,34 +1,177 @@
-use crate::error::RpcError;
+use crate::{circuit_breaker::CircuitBreaker, error::RpcError, metrics};
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, mpsc, oneshot, watch};
+use tokio_stream::{wrappers::ReceiverStream, StreamExt};
 use tonic::transport::Channel;
 
 // Include generated protobuf code
+#[allow(clippy::all)]
 pub mod proto {
     tonic::include_proto!("protowire");
 }
 
 use proto::{
+    kaspad_request::Payload as RequestPayload, kaspad_response::Payload as ResponsePayload,
     rpc_client::RpcClient, GetBlockRequestMessage, GetBlockDagInfoRequestMessage,
-    GetUtxosByAddressesRequestMessage, KaspadRequest, KaspadResponse, 
+    GetUtxosByAddressesRequestMessage, KaspadRequest, KaspadResponse,
     NotifyUtxosChangedRequestMessage, SubmitTransactionRequestMessage,
 };
 
+/// Notification pushed by the node, shared between all subscribers
+pub type Notification = Arc<ResponsePayload>;
+
+/// Outbound requests buffered before the stream applies backpressure
+const REQUEST_QUEUE_CAPACITY: usize = 1024;
+
+/// Notifications buffered per subscriber before it starts lagging
+const NOTIFICATION_CHANNEL_CAPACITY: usize = 4096;
+
+/// Delay before the first reconnection attempt
+const INITIAL_RECONNECT_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Upper bound on the delay between reconnection attempts
+const MAX_RECONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Deadline for status calls the node answers from memory
+const FAST_CALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Deadline for most calls
+const DEFAULT_CALL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Deadline for calls that walk the DAG, the UTXO index or the mempool
+const SLOW_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Extra attempts for calls that are safe to repeat
+const MAX_RETRIES: u32 = 2;
+
+/// Backoff before the first retry, doubled for each one after
+const RETRY_BACKOFF: Duration = Duration::from_millis(100);
+
+/// Callers waiting on a response, or on the stream error that ended it
+type PendingRequests = Mutex<HashMap<u64, oneshot::Sender<Result<KaspadResponse, RpcError>>>>;
+
+/// Notification registrations replayed on every new stream
+#[derive(Default)]
+struct Registrations {
+    utxo_addresses: HashSet<String>,
+    block_added: bool,
+    // Whether accepted transaction IDs are included, when registered
+    virtual_chain_changed: Option<bool>,
+    sink_blue_score_changed: bool,
+    virtual_daa_score_changed: bool,
+}
+
+impl Registrations {
+    fn replay(&self) -> Vec<RequestPayload> {
+        let mut payloads = Vec::new();
+        if !self.utxo_addresses.is_empty() {
+            payloads.push(RequestPayload::NotifyUtxosChangedRequest(
+                NotifyUtxosChangedRequestMessage {
+                    command: proto::RpcNotifyCommand::NotifyStart as i32,
+                    addresses: self.utxo_addresses.iter().cloned().collect(),
+                },
+            ));
+        }
+        if self.block_added {
+            payloads.push(RequestPayload::NotifyBlockAddedRequest(
+                proto::NotifyBlockAddedRequestMessage {
+                    command: proto::RpcNotifyCommand::NotifyStart as i32,
+                },
+            ));
+        }
+        if let Some(include_accepted_transaction_ids) = self.virtual_chain_changed {
+            payloads.push(RequestPayload::NotifyVirtualChainChangedRequest(
+                proto::NotifyVirtualChainChangedRequestMessage {
+                    include_accepted_transaction_ids,
+                    command: proto::RpcNotifyCommand::NotifyStart as i32,
+                },
+            ));
+        }
+        if self.sink_blue_score_changed {
+            payloads.push(RequestPayload::NotifySinkBlueScoreChangedRequest(
+                proto::NotifySinkBlueScoreChangedRequestMessage {
+                    command: proto::RpcNotifyCommand::NotifyStart as i32,
+                },
+            ));
+        }
+        if self.virtual_daa_score_changed {
+            payloads.push(RequestPayload::NotifyVirtualDaaScoreChangedRequest(
+                proto::NotifyVirtualDaaScoreChangedRequestMessage {
+                    command: proto::RpcNotifyCommand::NotifyStart as i32,
+                },
+            ));
+        }
+        payloads
+    }
+}
+
+/// State shared between the client handle and its connection task
+#[derive(Default)]
+struct Shared {
+    pending: PendingRequests,
+    registrations: Mutex<Registrations>,
+}
+
 /// High-performance gRPC client for Kaspa node
+///
+/// All calls are multiplexed over a single long-lived `MessageStream`.
+/// Responses are routed back to their caller by request ID, while
+/// notifications are fanned out to every notification subscriber.
+/// The stream is opened lazily and re-established with exponential
+/// backoff whenever it drops, replaying active registrations. Every
+/// call has a deadline, calls without side effects are retried, and a
+/// circuit breaker fails calls fast while the node keeps timing out.
 pub struct KaspaClient {
-    client: RpcClient<Channel>,
+    endpoint: String,
+    breaker: CircuitBreaker,
+    requests: mpsc::Sender<KaspadRequest>,
+    shared: Arc<Shared>,
+    notifications: broadcast::Sender<Notification>,
+    connected: watch::Receiver<bool>,
 }
 
 impl KaspaClient {
-    /// Create new client connection to Kaspa node
-    pub async fn new(endpoint: &str) -> Result<Self, RpcError> {
+    /// Create new client for Kaspa node; the connection is made in the background
+    pub fn new(endpoint: &str) -> Result<Self, RpcError> {
         let channel = Channel::from_shared(endpoint.to_string())
             .map_err(|e| RpcError::Connection(e.to_string()))?
-            .connect()
-            .await
-            .map_err(|e| RpcError::Connection(e.to_string()))?;
+            .connect_lazy();
 
-        let client = RpcClient::new(channel);
-        
-        Ok(Self { client })
+        let (requests, outbound) = mpsc::channel(REQUEST_QUEUE_CAPACITY);
+        let (notifications, _) = broadcast::channel(NOTIFICATION_CHANNEL_CAPACITY);
+        let (connected_tx, connected) = watch::channel(false);
+        let shared = Arc::new(Shared::default());
+
+        tokio::spawn(run_connection(
+            endpoint.to_string(),
+            RpcClient::new(channel),
+            outbound,
+            shared.clone(),
+            notifications.clone(),
+            connected_tx,
+        ));
+
+        Ok(Self {
+            endpoint: endpoint.to_string(),
+            breaker: CircuitBreaker::new(endpoint),
+            requests,
+            shared,
+            notifications,
+            connected,
+        })
+    }
+
+    /// Receive every notification the node pushes on the shared stream
+    pub fn notifications(&self) -> broadcast::Receiver<Notification> {
+        self.notifications.subscribe()
+    }
+
+    /// Watch the state of the underlying message stream
+    pub fn connection_state(&self) -> watch::Receiver<bool> {
+        self.connected.clone()
     }
 
     /// Get block by hash
@@ -51,7 +194,7 @@ impl KaspaClient {
             .send_request(request)
             .await?;
 
-        if let Some(proto::kaspad_response::Payload::GetBlockResponse(resp)) = response.payload {
+        if let Some(ResponsePayload::GetBlockResponse(resp)) = response.payload {
             if let Some(error) = &resp.error {
                 return Err(RpcError::Kaspa(error.message.clone()));
             }
@@ -61,6 +204,98 @@ impl KaspaClient {
         }
     }
 
+    /// Get the hashes (and optionally blocks) from `low_hash` up to the virtual
+    pub async fn get_blocks(
+        &self,
+        low_hash: String,
+        include_blocks: bool,
+        include_transactions: bool,
+    ) -> Result<proto::GetBlocksResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetBlocksRequest(
+                proto::GetBlocksRequestMessage {
+                    low_hash,
+                    include_blocks,
+                    include_transactions,
+                },
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetBlocksResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected GetBlocksResponse".into()))
+        }
+    }
+
+    /// Get up to `limit` header hashes starting at `start_hash`
+    pub async fn get_headers(
+        &self,
+        start_hash: String,
+        limit: u64,
+        is_ascending: bool,
+    ) -> Result<proto::GetHeadersResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetHeadersRequest(
+                proto::GetHeadersRequestMessage {
+                    start_hash,
+                    limit,
+                    is_ascending,
+                },
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetHeadersResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected GetHeadersResponse".into()))
+        }
+    }
+
+    /// Get the selected chain changes from `start_hash` up to the virtual
+    pub async fn get_virtual_chain_from_block(
+        &self,
+        start_hash: String,
+        include_accepted_transaction_ids: bool,
+        min_confirmation_count: Option<u64>,
+    ) -> Result<proto::GetVirtualChainFromBlockResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetVirtualChainFromBlockRequest(
+                proto::GetVirtualChainFromBlockRequestMessage {
+                    start_hash,
+                    include_accepted_transaction_ids,
+                    min_confirmation_count,
+                },
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetVirtualChainFromBlockResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse(
+                "Expected GetVirtualChainFromBlockResponse".into(),
+            ))
+        }
+    }
+
     /// Submit transaction to network
     pub async fn submit_transaction(
         &self,
@@ -79,7 +314,7 @@ impl KaspaClient {
 
         let response = self.send_request(request).await?;
 
-        if let Some(proto::kaspad_response::Payload::SubmitTransactionResponse(resp)) =
+        if let Some(ResponsePayload::SubmitTransactionResponse(resp)) =
             response.payload
         {
             if let Some(error) = &resp.error {
@@ -91,6 +326,37 @@ impl KaspaClient {
         }
     }
 
+    /// Submit a transaction replacing a mempool transaction that spends
+    /// the same outputs (RBF)
+    pub async fn submit_transaction_replacement(
+        &self,
+        transaction: proto::RpcTransaction,
+    ) -> Result<proto::SubmitTransactionReplacementResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::SubmitTransactionReplacementRequest(
+                proto::SubmitTransactionReplacementRequestMessage {
+                    transaction: Some(transaction),
+                },
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::SubmitTransactionReplacementResponse(resp)) =
+            response.payload
+        {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse(
+                "Expected SubmitTransactionReplacementResponse".into(),
+            ))
+        }
+    }
+
     /// Get DAG tips (chain heads)
     pub async fn get_dag_tips(&self) -> Result<proto::GetBlockDagInfoResponseMessage, RpcError> {
         let request = KaspadRequest {
@@ -102,7 +368,7 @@ impl KaspaClient {
 
         let response = self.send_request(request).await?;
 
-        if let Some(proto::kaspad_response::Payload::GetBlockDagInfoResponse(resp)) =
+        if let Some(ResponsePayload::GetBlockDagInfoResponse(resp)) =
             response.payload
         {
             if let Some(error) = &resp.error {
@@ -128,7 +394,7 @@ impl KaspaClient {
 
         let response = self.send_request(request).await?;
 
-        if let Some(proto::kaspad_response::Payload::GetUtxosByAddressesResponse(resp)) =
+        if let Some(ResponsePayload::GetUtxosByAddressesResponse(resp)) =
             response.payload
         {
             if let Some(error) = &resp.error {
@@ -140,50 +406,851 @@ impl KaspaClient {
         }
     }
 
-    /// Subscribe to UTXO changes (for WebSocket streaming)
-    pub async fn subscribe_utxo_changes(
+    /// Get the balance of one address
+    pub async fn get_balance_by_address(
+        &self,
+        address: String,
+    ) -> Result<proto::GetBalanceByAddressResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetBalanceByAddressRequest(
+                proto::GetBalanceByAddressRequestMessage { address },
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetBalanceByAddressResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected GetBalanceByAddressResponse".into()))
+        }
+    }
+
+    /// Get the balances of several addresses
+    pub async fn get_balances_by_addresses(
         &self,
         addresses: Vec<String>,
-    ) -> Result<tonic::Streaming<KaspadResponse>, RpcError> {
+    ) -> Result<proto::GetBalancesByAddressesResponseMessage, RpcError> {
         let request = KaspadRequest {
             id: generate_request_id(),
-            payload: Some(proto::kaspad_request::Payload::NotifyUtxosChangedRequest(
-                NotifyUtxosChangedRequestMessage { 
-                  command: 0,
-                  addresses,
+            payload: Some(proto::kaspad_request::Payload::GetBalancesByAddressesRequest(
+                proto::GetBalancesByAddressesRequestMessage { addresses },
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetBalancesByAddressesResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected GetBalancesByAddressesResponse".into()))
+        }
+    }
+
+    /// Get a mempool entry by transaction ID
+    pub async fn get_mempool_entry(
+        &self,
+        tx_id: String,
+        include_orphan_pool: bool,
+        filter_transaction_pool: bool,
+    ) -> Result<proto::GetMempoolEntryResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetMempoolEntryRequest(
+                proto::GetMempoolEntryRequestMessage {
+                    tx_id,
+                    include_orphan_pool,
+                    filter_transaction_pool,
                 },
             )),
         };
 
-        let stream = self
-            .client
-            .clone()
-            .message_stream(tokio_stream::once(request))
-            .await
-            .map_err(|e| RpcError::Connection(e.to_string()))?
-            .into_inner();
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetMempoolEntryResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected GetMempoolEntryResponse".into()))
+        }
+    }
+
+    /// Get all mempool entries
+    pub async fn get_mempool_entries(
+        &self,
+        include_orphan_pool: bool,
+        filter_transaction_pool: bool,
+    ) -> Result<proto::GetMempoolEntriesResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetMempoolEntriesRequest(
+                proto::GetMempoolEntriesRequestMessage {
+                    include_orphan_pool,
+                    filter_transaction_pool,
+                },
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetMempoolEntriesResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected GetMempoolEntriesResponse".into()))
+        }
+    }
+
+    /// Get the mempool entries sending from or to a set of addresses
+    pub async fn get_mempool_entries_by_addresses(
+        &self,
+        addresses: Vec<String>,
+        include_orphan_pool: bool,
+        filter_transaction_pool: bool,
+    ) -> Result<proto::GetMempoolEntriesByAddressesResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetMempoolEntriesByAddressesRequest(
+                proto::GetMempoolEntriesByAddressesRequestMessage {
+                    addresses,
+                    include_orphan_pool,
+                    filter_transaction_pool,
+                },
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetMempoolEntriesByAddressesResponse(resp)) =
+            response.payload
+        {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse(
+                "Expected GetMempoolEntriesByAddressesResponse".into(),
+            ))
+        }
+    }
+
+    /// Get fee rate estimates per priority tier
+    pub async fn get_fee_estimate(&self) -> Result<proto::GetFeeEstimateResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetFeeEstimateRequest(
+                proto::GetFeeEstimateRequestMessage {},
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetFeeEstimateResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected GetFeeEstimateResponse".into()))
+        }
+    }
+
+    /// Get fee rate estimates along with the experimental mempool data
+    pub async fn get_fee_estimate_experimental(
+        &self,
+        verbose: bool,
+    ) -> Result<proto::GetFeeEstimateExperimentalResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetFeeEstimateExperimentalRequest(
+                proto::GetFeeEstimateExperimentalRequestMessage { verbose },
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetFeeEstimateExperimentalResponse(resp)) =
+            response.payload
+        {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse(
+                "Expected GetFeeEstimateExperimentalResponse".into(),
+            ))
+        }
+    }
+
+    /// Get node version, mempool size and capabilities
+    pub async fn get_info(&self) -> Result<proto::GetInfoResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetInfoRequest(
+                proto::GetInfoRequestMessage {},
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetInfoResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected GetInfoResponse".into()))
+        }
+    }
+
+    /// Get RPC API version, network and sync state
+    pub async fn get_server_info(&self) -> Result<proto::GetServerInfoResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetServerInfoRequest(
+                proto::GetServerInfoRequestMessage {},
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetServerInfoResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected GetServerInfoResponse".into()))
+        }
+    }
+
+    /// Ping the node (liveness probe)
+    pub async fn ping(&self) -> Result<proto::PingResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::PingRequest(
+                proto::PingRequestMessage {},
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::PingResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected PingResponse".into()))
+        }
+    }
+
+    /// Get node sync status
+    pub async fn get_sync_status(&self) -> Result<proto::GetSyncStatusResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetSyncStatusRequest(
+                proto::GetSyncStatusRequestMessage {},
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetSyncStatusResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected GetSyncStatusResponse".into()))
+        }
+    }
+
+    /// Get the network the node runs on
+    pub async fn get_current_network(&self) -> Result<proto::GetCurrentNetworkResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetCurrentNetworkRequest(
+                proto::GetCurrentNetworkRequestMessage {},
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetCurrentNetworkResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected GetCurrentNetworkResponse".into()))
+        }
+    }
+
+    /// Get the maximum and circulating coin supply
+    pub async fn get_coin_supply(&self) -> Result<proto::GetCoinSupplyResponseMessage, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(proto::kaspad_request::Payload::GetCoinSupplyRequest(
+                proto::GetCoinSupplyRequestMessage {},
+            )),
+        };
+
+        let response = self.send_request(request).await?;
+
+        if let Some(ResponsePayload::GetCoinSupplyResponse(resp)) = response.payload {
+            if let Some(error) = &resp.error {
+                return Err(RpcError::Kaspa(error.message.clone()));
+            }
+            Ok(resp)
+        } else {
+            Err(RpcError::InvalidResponse("Expected GetCoinSupplyResponse".into()))
+        }
+    }
+
+    /// Start UTXO change notifications for addresses
+    ///
+    /// Notifications arrive on [`KaspaClient::notifications`]. The
+    /// registration survives reconnects until it is stopped.
+    pub async fn subscribe_utxo_changes(&self, addresses: Vec<String>) -> Result<(), RpcError> {
+        // Record first so a reconnect racing this call still replays it
+        self.shared
+            .registrations
+            .lock()
+            .unwrap()
+            .utxo_addresses
+            .extend(addresses.iter().cloned());
+
+        self.send_notify_request(RequestPayload::NotifyUtxosChangedRequest(
+            NotifyUtxosChangedRequestMessage {
+                command: proto::RpcNotifyCommand::NotifyStart as i32,
+                addresses,
+            },
+        ))
+        .await
+    }
+
+    /// Stop UTXO change notifications for addresses
+    pub async fn unsubscribe_utxo_changes(&self, addresses: Vec<String>) -> Result<(), RpcError> {
+        {
+            let mut registrations = self.shared.registrations.lock().unwrap();
+            for address in &addresses {
+                registrations.utxo_addresses.remove(address);
+            }
+        }
+
+        self.send_notify_request(RequestPayload::NotifyUtxosChangedRequest(
+            NotifyUtxosChangedRequestMessage {
+                command: proto::RpcNotifyCommand::NotifyStop as i32,
+                addresses,
+            },
+        ))
+        .await
+    }
+
+    /// Start block-added notifications
+    pub async fn subscribe_block_added(&self) -> Result<(), RpcError> {
+        self.shared.registrations.lock().unwrap().block_added = true;
+
+        self.send_notify_request(RequestPayload::NotifyBlockAddedRequest(
+            proto::NotifyBlockAddedRequestMessage {
+                command: proto::RpcNotifyCommand::NotifyStart as i32,
+            },
+        ))
+        .await
+    }
+
+    /// Stop block-added notifications
+    pub async fn unsubscribe_block_added(&self) -> Result<(), RpcError> {
+        self.shared.registrations.lock().unwrap().block_added = false;
+
+        self.send_notify_request(RequestPayload::NotifyBlockAddedRequest(
+            proto::NotifyBlockAddedRequestMessage {
+                command: proto::RpcNotifyCommand::NotifyStop as i32,
+            },
+        ))
+        .await
+    }
+
+    /// Start (or re-scope) virtual-chain-changed notifications
+    pub async fn subscribe_virtual_chain_changed(
+        &self,
+        include_accepted_transaction_ids: bool,
+    ) -> Result<(), RpcError> {
+        self.shared.registrations.lock().unwrap().virtual_chain_changed =
+            Some(include_accepted_transaction_ids);
 
-        Ok(stream)
+        self.send_notify_request(RequestPayload::NotifyVirtualChainChangedRequest(
+            proto::NotifyVirtualChainChangedRequestMessage {
+                include_accepted_transaction_ids,
+                command: proto::RpcNotifyCommand::NotifyStart as i32,
+            },
+        ))
+        .await
     }
 
-    /// Internal helper to send request and get single response
+    /// Stop virtual-chain-changed notifications
+    pub async fn unsubscribe_virtual_chain_changed(&self) -> Result<(), RpcError> {
+        self.shared.registrations.lock().unwrap().virtual_chain_changed = None;
+
+        self.send_notify_request(RequestPayload::NotifyVirtualChainChangedRequest(
+            proto::NotifyVirtualChainChangedRequestMessage {
+                include_accepted_transaction_ids: false,
+                command: proto::RpcNotifyCommand::NotifyStop as i32,
+            },
+        ))
+        .await
+    }
+
+    /// Start sink-blue-score-changed notifications
+    pub async fn subscribe_sink_blue_score_changed(&self) -> Result<(), RpcError> {
+        self.shared.registrations.lock().unwrap().sink_blue_score_changed = true;
+
+        self.send_notify_request(RequestPayload::NotifySinkBlueScoreChangedRequest(
+            proto::NotifySinkBlueScoreChangedRequestMessage {
+                command: proto::RpcNotifyCommand::NotifyStart as i32,
+            },
+        ))
+        .await
+    }
+
+    /// Stop sink-blue-score-changed notifications
+    pub async fn unsubscribe_sink_blue_score_changed(&self) -> Result<(), RpcError> {
+        self.shared.registrations.lock().unwrap().sink_blue_score_changed = false;
+
+        self.send_notify_request(RequestPayload::NotifySinkBlueScoreChangedRequest(
+            proto::NotifySinkBlueScoreChangedRequestMessage {
+                command: proto::RpcNotifyCommand::NotifyStop as i32,
+            },
+        ))
+        .await
+    }
+
+    /// Start virtual-DAA-score-changed notifications
+    pub async fn subscribe_virtual_daa_score_changed(&self) -> Result<(), RpcError> {
+        self.shared.registrations.lock().unwrap().virtual_daa_score_changed = true;
+
+        self.send_notify_request(RequestPayload::NotifyVirtualDaaScoreChangedRequest(
+            proto::NotifyVirtualDaaScoreChangedRequestMessage {
+                command: proto::RpcNotifyCommand::NotifyStart as i32,
+            },
+        ))
+        .await
+    }
+
+    /// Stop virtual-DAA-score-changed notifications
+    pub async fn unsubscribe_virtual_daa_score_changed(&self) -> Result<(), RpcError> {
+        self.shared.registrations.lock().unwrap().virtual_daa_score_changed = false;
+
+        self.send_notify_request(RequestPayload::NotifyVirtualDaaScoreChangedRequest(
+            proto::NotifyVirtualDaaScoreChangedRequestMessage {
+                command: proto::RpcNotifyCommand::NotifyStop as i32,
+            },
+        ))
+        .await
+    }
+
+    /// Send any request payload and return the node's response payload
+    ///
+    /// Errors reported inside the response are left for the caller to
+    /// interpret; only transport failures and empty responses fail here.
+    pub async fn call(&self, payload: RequestPayload) -> Result<ResponsePayload, RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(payload),
+        };
+
+        let response = self.send_request(request).await?;
+
+        response
+            .payload
+            .ok_or_else(|| RpcError::InvalidResponse("Empty response payload".into()))
+    }
+
+    /// Whether the message stream is currently open
+    pub fn is_connected(&self) -> bool {
+        *self.connected.borrow()
+    }
+
+    /// Send a notification registration and check the node accepted it
+    async fn send_notify_request(&self, payload: RequestPayload) -> Result<(), RpcError> {
+        let request = KaspadRequest {
+            id: generate_request_id(),
+            payload: Some(payload),
+        };
+
+        match self.send_request(request).await {
+            Ok(response) => match response_error(&response) {
+                Some(message) => Err(RpcError::Kaspa(message)),
+                None => Ok(()),
+            },
+            // Registrations are replayed once the stream is back up, so only
+            // a missing stream is no error; an open circuit, say, is
+            Err(RpcError::Connection(_)) if !self.is_connected() => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Internal helper to send request and await its correlated response
+    ///
+    /// Calls without side effects are retried with jittered backoff when
+    /// the node times out or the stream drops mid-call; anything else,
+    /// like `submitTransaction`, gets exactly one attempt. Should such a
+    /// call be lost after it was sent, it fails as `Unconfirmed` so the
+    /// pool does not send it to another node.
     async fn send_request(&self, request: KaspadRequest) -> Result<KaspadResponse, RpcError> {
-        use tokio_stream::StreamExt;
+        let payload = request.payload.as_ref();
+        let timeout = payload.map_or(DEFAULT_CALL_TIMEOUT, call_timeout);
+        let retry_payload = payload.filter(|p| is_idempotent(p)).cloned();
 
-        let mut stream = self
-            .client
-            .clone()
-            .message_stream(tokio_stream::once(request))
-            .await
-            .map_err(|e| RpcError::Connection(e.to_string()))?
-            .into_inner();
+        let mut request = request;
+        let mut attempt = 0;
+        loop {
+            let error = match self.send_once(request, timeout).await {
+                Ok(response) => {
+                    self.breaker.record_success();
+                    return Ok(response);
+                }
+                Err(CallError::NotSent(error)) => return Err(error),
+                Err(CallError::Failed(error)) => {
+                    self.breaker.record_failure();
+                    error
+                }
+            };
+
+            if retry_payload.is_none() {
+                return Err(match error {
+                    RpcError::Connection(msg) | RpcError::Timeout(msg) => {
+                        RpcError::Unconfirmed(msg)
+                    }
+                    error => error,
+                });
+            }
+            let Some(payload) = retry_payload.clone().filter(|_| attempt < MAX_RETRIES) else {
+                return Err(error);
+            };
 
-        stream
-            .next()
-            .await
-            .ok_or_else(|| RpcError::InvalidResponse("Empty response stream".into()))?
-            .map_err(|e| RpcError::Connection(e.to_string()))
+            let backoff = RETRY_BACKOFF * 2u32.pow(attempt);
+            let delay = rand::thread_rng().gen_range(backoff / 2..=backoff);
+            tracing::debug!(upstream = %self.endpoint, "Retrying in {:?}: {}", delay, error);
+            metrics::record_upstream_retry(&self.endpoint);
+            tokio::time::sleep(delay).await;
+
+            // A fresh ID keeps a late response to the last attempt from being taken for this one
+            request = KaspadRequest {
+                id: generate_request_id(),
+                payload: Some(payload),
+            };
+            attempt += 1;
+        }
     }
+
+    /// Make one attempt at a call
+    async fn send_once(
+        &self,
+        request: KaspadRequest,
+        timeout: Duration,
+    ) -> Result<KaspadResponse, CallError> {
+        // Fail fast so callers can fail over instead of queueing behind a reconnect
+        if !self.is_connected() {
+            return Err(CallError::NotSent(RpcError::Connection(
+                "Not connected to Kaspa node".into(),
+            )));
+        }
+        if !self.breaker.admit() {
+            return Err(CallError::NotSent(RpcError::Connection(
+                "Circuit open after repeated failures".into(),
+            )));
+        }
+
+        let (sender, receiver) = oneshot::channel();
+        let _guard = PendingGuard::register(&self.shared.pending, request.id, sender);
+
+        self.requests.send(request).await.map_err(|_| {
+            CallError::NotSent(RpcError::Connection("Message stream closed".into()))
+        })?;
+
+        match tokio::time::timeout(timeout, receiver).await {
+            Ok(Ok(Ok(response))) => Ok(response),
+            Ok(Ok(Err(error))) => Err(CallError::Failed(error)),
+            Ok(Err(_)) => Err(CallError::Failed(RpcError::Connection(
+                "Message stream closed before response".into(),
+            ))),
+            Err(_) => {
+                metrics::record_upstream_timeout(&self.endpoint);
+                Err(CallError::Failed(RpcError::Timeout(format!(
+                    "No response within {:?}",
+                    timeout
+                ))))
+            }
+        }
+    }
+}
+
+/// Why a single attempt at a call failed
+enum CallError {
+    /// The request never reached the node
+    NotSent(RpcError),
+    /// The request was sent but no response came back
+    Failed(RpcError),
+}
+
+/// Deadline for a call, by how much work the node does to answer it
+fn call_timeout(payload: &RequestPayload) -> Duration {
+    match payload {
+        RequestPayload::PingRequest(_)
+        | RequestPayload::GetInfoRequest(_)
+        | RequestPayload::GetServerInfoRequest(_)
+        | RequestPayload::GetSyncStatusRequest(_)
+        | RequestPayload::GetCurrentNetworkRequest(_) => FAST_CALL_TIMEOUT,
+        RequestPayload::GetBlocksRequest(_)
+        | RequestPayload::GetHeadersRequest(_)
+        | RequestPayload::GetVirtualChainFromBlockRequest(_)
+        | RequestPayload::GetUtxosByAddressesRequest(_)
+        | RequestPayload::GetBalancesByAddressesRequest(_)
+        | RequestPayload::GetMempoolEntriesRequest(_)
+        | RequestPayload::GetMempoolEntriesByAddressesRequest(_)
+        | RequestPayload::EstimateNetworkHashesPerSecondRequest(_) => SLOW_CALL_TIMEOUT,
+        _ => DEFAULT_CALL_TIMEOUT,
+    }
+}
+
+/// Whether a call can be repeated without side effects
+fn is_idempotent(payload: &RequestPayload) -> bool {
+    !matches!(
+        payload,
+        RequestPayload::SubmitTransactionRequest(_)
+            | RequestPayload::SubmitTransactionReplacementRequest(_)
+            | RequestPayload::SubmitBlockRequest(_)
+            | RequestPayload::AddPeerRequest(_)
+            | RequestPayload::BanRequest(_)
+            | RequestPayload::UnbanRequest(_)
+            | RequestPayload::ShutdownRequest(_)
+            | RequestPayload::ResolveFinalityConflictRequest(_)
+    )
+}
+
+/// Removes a pending request entry if its caller goes away before the response
+struct PendingGuard<'a> {
+    pending: &'a PendingRequests,
+    id: u64,
+}
+
+impl<'a> PendingGuard<'a> {
+    fn register(
+        pending: &'a PendingRequests,
+        id: u64,
+        sender: oneshot::Sender<Result<KaspadResponse, RpcError>>,
+    ) -> Self {
+        pending.lock().unwrap().insert(id, sender);
+        Self { pending, id }
+    }
+}
+
+impl Drop for PendingGuard<'_> {
+    fn drop(&mut self) {
+        self.pending.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Keep a message stream open, reconnecting with exponential backoff
+async fn run_connection(
+    endpoint: String,
+    mut rpc: RpcClient<Channel>,
+    mut requests: mpsc::Receiver<KaspadRequest>,
+    shared: Arc<Shared>,
+    notifications: broadcast::Sender<Notification>,
+    connected: watch::Sender<bool>,
+) {
+    let mut backoff = INITIAL_RECONNECT_BACKOFF;
+
+    loop {
+        let (outbound, stream_requests) = mpsc::channel(REQUEST_QUEUE_CAPACITY);
+
+        match rpc.message_stream(ReceiverStream::new(stream_requests)).await {
+            Ok(response) => {
+                tracing::info!("✓ Message stream open to {}", endpoint);
+                backoff = INITIAL_RECONNECT_BACKOFF;
+
+                replay_registrations(&shared, &outbound).await;
+                let _ = connected.send(true);
+
+                let client_dropped = pump_stream(
+                    response.into_inner(),
+                    &mut requests,
+                    &outbound,
+                    &shared,
+                    &notifications,
+                    &connected,
+                )
+                .await;
+
+                let _ = connected.send(false);
+
+                // Dropping the senders wakes every waiting caller with an error
+                shared.pending.lock().unwrap().clear();
+                while requests.try_recv().is_ok() {}
+
+                if client_dropped {
+                    return;
+                }
+                tracing::warn!("Message stream to {} closed", endpoint);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to open message stream to {}: {}", endpoint, e);
+            }
+        }
+
+        tracing::info!("Reconnecting to {} in {:?}", endpoint, backoff);
+        tokio::time::sleep(backoff).await;
+        backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+    }
+}
+
+/// Forward requests and route responses until the stream ends
+///
+/// Returns `true` when the client handle was dropped.
+async fn pump_stream(
+    mut inbound: tonic::Streaming<KaspadResponse>,
+    requests: &mut mpsc::Receiver<KaspadRequest>,
+    outbound: &mpsc::Sender<KaspadRequest>,
+    shared: &Shared,
+    notifications: &broadcast::Sender<Notification>,
+    connected: &watch::Sender<bool>,
+) -> bool {
+    loop {
+        tokio::select! {
+            request = requests.recv() => match request {
+                Some(request) => {
+                    if outbound.send(request).await.is_err() {
+                        return false;
+                    }
+                }
+                None => return true,
+            },
+            message = inbound.next() => match message {
+                Some(Ok(response)) => route_response(response, shared, notifications),
+                Some(Err(status)) => {
+                    tracing::error!("Message stream error: {}", status);
+                    // Callers woken by the failure must see the stream as down
+                    let _ = connected.send(false);
+                    fail_pending(shared, &status);
+                    return false;
+                }
+                None => return false,
+            },
+        }
+    }
+}
+
+/// Deliver a response to its waiting caller, or a notification to subscribers
+fn route_response(
+    response: KaspadResponse,
+    shared: &Shared,
+    notifications: &broadcast::Sender<Notification>,
+) {
+    match response.payload {
+        Some(payload) if is_notification(&payload) => {
+            // No receivers simply means nobody is subscribed right now
+            let _ = notifications.send(Arc::new(payload));
+        }
+        _ => {
+            let waiter = shared.pending.lock().unwrap().remove(&response.id);
+            match waiter {
+                Some(sender) => {
+                    let _ = sender.send(Ok(response));
+                }
+                None => tracing::warn!(id = response.id, "Dropping uncorrelated response"),
+            }
+        }
+    }
+}
+
+/// Fail every waiting caller with the error that broke the stream
+fn fail_pending(shared: &Shared, status: &tonic::Status) {
+    let pending: Vec<_> = shared.pending.lock().unwrap().drain().collect();
+    for (_, sender) in pending {
+        let _ = sender.send(Err(RpcError::from(status.clone())));
+    }
+}
+
+/// Re-register active notifications on a freshly opened stream
+async fn replay_registrations(shared: &Arc<Shared>, outbound: &mpsc::Sender<KaspadRequest>) {
+    let payloads = shared.registrations.lock().unwrap().replay();
+
+    for payload in payloads {
+        let id = generate_request_id();
+        let (sender, receiver) = oneshot::channel();
+        shared.pending.lock().unwrap().insert(id, sender);
+
+        let request = KaspadRequest {
+            id,
+            payload: Some(payload),
+        };
+        if outbound.send(request).await.is_err() {
+            shared.pending.lock().unwrap().remove(&id);
+            return;
+        }
+
+        tokio::spawn(async move {
+            if let Ok(Ok(response)) = receiver.await {
+                if let Some(error) = response_error(&response) {
+                    tracing::error!("Failed to re-register notifications: {}", error);
+                }
+            }
+        });
+    }
+}
+
+/// Error carried by a notification registration response, if any
+fn response_error(response: &KaspadResponse) -> Option<String> {
+    match &response.payload {
+        Some(ResponsePayload::NotifyUtxosChangedResponse(resp)) => {
+            resp.error.as_ref().map(|e| e.message.clone())
+        }
+        Some(ResponsePayload::NotifyBlockAddedResponse(resp)) => {
+            resp.error.as_ref().map(|e| e.message.clone())
+        }
+        Some(ResponsePayload::NotifyVirtualChainChangedResponse(resp)) => {
+            resp.error.as_ref().map(|e| e.message.clone())
+        }
+        Some(ResponsePayload::NotifySinkBlueScoreChangedResponse(resp)) => {
+            resp.error.as_ref().map(|e| e.message.clone())
+        }
+        Some(ResponsePayload::NotifyVirtualDaaScoreChangedResponse(resp)) => {
+            resp.error.as_ref().map(|e| e.message.clone())
+        }
+        _ => None,
+    }
+}
+
+/// Whether a payload is a node-initiated notification rather than a response
+fn is_notification(payload: &ResponsePayload) -> bool {
+    matches!(
+        payload,
+        ResponsePayload::BlockAddedNotification(_)
+            | ResponsePayload::VirtualChainChangedNotification(_)
+            | ResponsePayload::FinalityConflictNotification(_)
+            | ResponsePayload::FinalityConflictResolvedNotification(_)
+            | ResponsePayload::UtxosChangedNotification(_)
+            | ResponsePayload::SinkBlueScoreChangedNotification(_)
+            | ResponsePayload::VirtualDaaScoreChangedNotification(_)
+            | ResponsePayload::PruningPointUtxoSetOverrideNotification(_)
+            | ResponsePayload::NewBlockTemplateNotification(_)
+    )
 }
 
 /// Generate unique request ID
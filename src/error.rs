@@ -1,15 +1,22 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
+use crate::request_id;
 use serde::Serialize;
 
-#[derive(Debug, thiserror::Error)]
+#[derive(Debug, Clone, thiserror::Error)]
 pub enum RpcError {
     #[error("Connection error: {0}")]
     Connection(String),
 
+    #[error("Upstream timeout: {0}")]
+    Timeout(String),
+
+    #[error("Outcome unknown: {0}")]
+    Unconfirmed(String),
+
     #[error("Kaspa node error: {0}")]
     Kaspa(String),
 
@@ -19,36 +26,231 @@ pub enum RpcError {
     #[error("Authentication error: {0}")]
     Auth(String),
 
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
     #[error("Invalid request: {0}")]
     BadRequest(String),
 
+    #[error("Invalid request: {} invalid fields", .0.len())]
+    Validation(Vec<FieldError>),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Rate limit exceeded, retry after {0}s")]
+    RateLimited(u64),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
-    code: u16,
+/// Why one field of a request was rejected
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    /// Path of the field, e.g. `transaction.inputs[0].signatureScript`
+    pub field: String,
+    pub message: String,
+}
+
+/// Stable, machine-readable error codes returned as `errorCode`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    BadRequest,
+    ValidationFailed,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    BlockNotFound,
+    TransactionNotFound,
+    RateLimited,
+    /// Transaction spends outputs the node does not know, and orphans
+    /// were not allowed
+    TxOrphan,
+    TxAlreadyInMempool,
+    /// Transaction spends an output already spent in the mempool
+    TxDoubleSpend,
+    TxInsufficientFee,
+    /// Transaction failed another consensus or mempool rule
+    TxRejected,
+    NodeNotSynced,
+    /// Any other error reported by the node
+    NodeError,
+    UpstreamUnavailable,
+    UpstreamTimeout,
+    /// A call with side effects reached the node but got no answer, so
+    /// it may or may not have taken effect
+    UpstreamOutcomeUnknown,
+    InvalidUpstreamResponse,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn status(self) -> StatusCode {
+        match self {
+            ErrorCode::BadRequest
+            | ErrorCode::ValidationFailed
+            | ErrorCode::TxOrphan
+            | ErrorCode::TxInsufficientFee
+            | ErrorCode::TxRejected
+            | ErrorCode::NodeError => StatusCode::BAD_REQUEST,
+            ErrorCode::Unauthorized => StatusCode::UNAUTHORIZED,
+            ErrorCode::Forbidden => StatusCode::FORBIDDEN,
+            ErrorCode::NotFound | ErrorCode::BlockNotFound | ErrorCode::TransactionNotFound => {
+                StatusCode::NOT_FOUND
+            }
+            ErrorCode::TxAlreadyInMempool | ErrorCode::TxDoubleSpend => StatusCode::CONFLICT,
+            ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::NodeNotSynced => StatusCode::SERVICE_UNAVAILABLE,
+            ErrorCode::UpstreamUnavailable => StatusCode::BAD_GATEWAY,
+            ErrorCode::UpstreamTimeout | ErrorCode::UpstreamOutcomeUnknown => {
+                StatusCode::GATEWAY_TIMEOUT
+            }
+            ErrorCode::InvalidUpstreamResponse | ErrorCode::Internal => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+
+    /// Classify an error message reported by kaspad
+    ///
+    /// The node only sends free text, so this matches on the wording of
+    /// its consensus and mempool errors.
+    fn from_node_message(message: &str) -> Self {
+        let message = message.to_lowercase();
+        let has = |needle: &str| message.contains(needle);
+        // Submission failures read "Rejected transaction <id>: <reason>"
+        let rejected = has("reject");
+
+        if has("not synced") {
+            ErrorCode::NodeNotSynced
+        } else if has("already spent") || has("double spend") {
+            ErrorCode::TxDoubleSpend
+        } else if has("already") && (has("mempool") || has("accepted")) {
+            ErrorCode::TxAlreadyInMempool
+        } else if !rejected && (has("not found") || has("cannot find")) {
+            if has("block") || has("header") {
+                ErrorCode::BlockNotFound
+            } else if has("transaction") {
+                ErrorCode::TransactionNotFound
+            } else {
+                ErrorCode::NotFound
+            }
+        } else if has("orphan") {
+            ErrorCode::TxOrphan
+        } else if has("fee") && (has("under") || has("insufficient") || has("too low")) {
+            ErrorCode::TxInsufficientFee
+        } else if rejected {
+            ErrorCode::TxRejected
+        } else {
+            ErrorCode::NodeError
+        }
+    }
+}
+
+impl RpcError {
+    /// Machine-readable code, which also decides the HTTP status
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            RpcError::Connection(_) => ErrorCode::UpstreamUnavailable,
+            RpcError::Timeout(_) => ErrorCode::UpstreamTimeout,
+            RpcError::Unconfirmed(_) => ErrorCode::UpstreamOutcomeUnknown,
+            RpcError::Kaspa(msg) => ErrorCode::from_node_message(msg),
+            RpcError::InvalidResponse(_) => ErrorCode::InvalidUpstreamResponse,
+            RpcError::Auth(_) => ErrorCode::Unauthorized,
+            RpcError::Forbidden(_) => ErrorCode::Forbidden,
+            RpcError::BadRequest(_) => ErrorCode::BadRequest,
+            RpcError::Validation(_) => ErrorCode::ValidationFailed,
+            RpcError::NotFound(_) => ErrorCode::NotFound,
+            RpcError::RateLimited(_) => ErrorCode::RateLimited,
+            RpcError::Internal(_) => ErrorCode::Internal,
+        }
+    }
+}
+
+/// Error envelope of failed requests, also used for the failed items
+/// of batches and for WebSocket error frames
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorBody {
+    pub error: String,
+    /// HTTP status code
+    pub code: u16,
+    pub error_code: ErrorCode,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+}
+
+impl From<RpcError> for ErrorBody {
+    fn from(error: RpcError) -> Self {
+        let error_code = error.code();
+        let (message, fields) = match error {
+            RpcError::Validation(fields) => (format!("{} invalid fields", fields.len()), fields),
+            RpcError::RateLimited(secs) => (
+                format!("Rate limit exceeded, retry after {}s", secs),
+                Vec::new(),
+            ),
+            RpcError::Connection(msg)
+            | RpcError::Timeout(msg)
+            | RpcError::Unconfirmed(msg)
+            | RpcError::Kaspa(msg)
+            | RpcError::InvalidResponse(msg)
+            | RpcError::Auth(msg)
+            | RpcError::Forbidden(msg)
+            | RpcError::BadRequest(msg)
+            | RpcError::NotFound(msg)
+            | RpcError::Internal(msg) => (msg, Vec::new()),
+        };
+
+        Self {
+            error: message,
+            code: error_code.status().as_u16(),
+            error_code,
+            request_id: request_id::current(),
+            fields,
+        }
+    }
 }
 
 impl IntoResponse for RpcError {
     fn into_response(self) -> Response {
-        let (status, message) = match self {
-            RpcError::Connection(msg) => (StatusCode::BAD_GATEWAY, msg),
-            RpcError::Kaspa(msg) => (StatusCode::BAD_REQUEST, msg),
-            RpcError::InvalidResponse(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
-            RpcError::Auth(msg) => (StatusCode::UNAUTHORIZED, msg),
-            RpcError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
-            RpcError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
+        let retry_after = match &self {
+            RpcError::RateLimited(secs) => Some(*secs),
+            _ => None,
         };
 
-        let body = Json(ErrorResponse {
-            error: message,
-            code: status.as_u16(),
-        });
+        let status = self.code().status();
+        let body = Json(ErrorBody::from(self));
 
-        (status, body).into_response()
+        match retry_after {
+            Some(secs) => (status, [(header::RETRY_AFTER, secs.to_string())], body).into_response(),
+            None => (status, body).into_response(),
+        }
+    }
+}
+
+/// Statuses end the message stream as a whole; the node reports errors
+/// of single calls inside their responses. So any status is a transport
+/// failure worth failing over, except a refusal that every retry with
+/// the same credentials would meet again.
+impl From<tonic::Status> for RpcError {
+    fn from(status: tonic::Status) -> Self {
+        use tonic::Code;
+
+        let message = status.message().to_string();
+        match status.code() {
+            Code::DeadlineExceeded => RpcError::Timeout(message),
+            Code::Unavailable | Code::Cancelled | Code::Aborted | Code::ResourceExhausted => {
+                RpcError::Connection(message)
+            }
+            Code::Unauthenticated | Code::PermissionDenied => {
+                RpcError::Internal(format!("Node refused access: {}", message))
+            }
+            _ => RpcError::Connection(format!("{}: {}", status.code(), message)),
+        }
     }
 }
 
@@ -57,3 +259,75 @@ impl From<anyhow::Error> for RpcError {
         RpcError::Internal(err.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_node_messages() {
+        let cases = [
+            ("Node is not synced", ErrorCode::NodeNotSynced),
+            (
+                "Rejected transaction abc: transaction abc is an orphan",
+                ErrorCode::TxOrphan,
+            ),
+            (
+                "Rejected transaction abc: output def:0 already spent by transaction 123 in the memory pool",
+                ErrorCode::TxDoubleSpend,
+            ),
+            (
+                "Rejected transaction abc: transaction abc is already in the mempool",
+                ErrorCode::TxAlreadyInMempool,
+            ),
+            (
+                "Rejected transaction abc: transaction abc has 100 fees which is under the required amount of 2036",
+                ErrorCode::TxInsufficientFee,
+            ),
+            (
+                "Rejected transaction abc: transaction has an invalid signature",
+                ErrorCode::TxRejected,
+            ),
+            ("Block 0123 not found", ErrorCode::BlockNotFound),
+            ("Cannot find header 0123", ErrorCode::BlockNotFound),
+            ("Transaction abc not found in mempool", ErrorCode::TransactionNotFound),
+            ("Address not found", ErrorCode::NotFound),
+            ("Method unavailable", ErrorCode::NodeError),
+        ];
+        for (message, code) in cases {
+            assert_eq!(ErrorCode::from_node_message(message), code, "{}", message);
+        }
+    }
+
+    #[test]
+    fn rejections_mentioning_missing_inputs_are_not_lookups() {
+        // "Rejected" wins over "not found" in the wording of a failed submission
+        assert_eq!(
+            ErrorCode::from_node_message("Rejected transaction abc: input def:0 not found"),
+            ErrorCode::TxRejected
+        );
+    }
+
+    #[test]
+    fn node_errors_map_to_their_status() {
+        let error = RpcError::Kaspa("Block 0123 not found".into());
+        assert_eq!(error.code(), ErrorCode::BlockNotFound);
+        assert_eq!(error.code().status(), StatusCode::NOT_FOUND);
+
+        let error = RpcError::Kaspa("Node is not synced".into());
+        assert_eq!(error.code().status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[test]
+    fn error_body_keeps_code_and_fields() {
+        let body = ErrorBody::from(RpcError::Validation(vec![FieldError {
+            field: "transaction.version".into(),
+            message: "Unsupported version".into(),
+        }]));
+        assert_eq!(body.code, 400);
+        assert_eq!(body.error_code, ErrorCode::ValidationFailed);
+        assert_eq!(body.fields.len(), 1);
+        // Outside a request there is no ID to report
+        assert!(body.request_id.is_none());
+    }
+}
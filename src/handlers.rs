@@ -1,14 +1,87 @@
-use crate::{auth, client, error::RpcError, metrics, models::*, AppState};
+use crate::{
+    address, auth, client, error::RpcError, metrics, models::*, transaction::ValidatedTransaction,
+    AppState,
+};
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{Path, State},
     Json,
 };
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
 use std::time::Instant;
+use tokio::{sync::Semaphore, task::JoinSet};
+
+/// Upper bound on addresses in one UTXO lookup
+pub const MAX_UTXO_ADDRESSES: usize = 1000;
+
+/// UTXOs returned per page unless the caller asks otherwise
+const DEFAULT_UTXO_PAGE_SIZE: usize = 1000;
+
+/// Upper bound on UTXOs returned per page
+const MAX_UTXO_PAGE_SIZE: usize = 10_000;
+
+/// Upper bound on addresses in one balance lookup
+pub const MAX_BALANCE_ADDRESSES: usize = 1000;
+
+/// Mempool entries returned per page unless the caller asks otherwise
+const DEFAULT_MEMPOOL_PAGE_SIZE: usize = 100;
+
+/// Upper bound on mempool entries returned per page
+const MAX_MEMPOOL_PAGE_SIZE: usize = 1000;
+
+/// Upper bound on addresses in one mempool lookup
+pub const MAX_MEMPOOL_ADDRESSES: usize = 1000;
+
+/// Upper bound on hashes in one batch block fetch
+const MAX_BLOCK_BATCH: usize = 100;
+
+/// Block fetches of one batch in flight at once
+const BLOCK_BATCH_CONCURRENCY: usize = 8;
+
+/// Hashes returned per block range page unless the caller asks otherwise
+const DEFAULT_BLOCK_RANGE_PAGE_SIZE: usize = 100;
+
+/// Upper bound on hashes returned per block range page
+const MAX_BLOCK_RANGE_PAGE_SIZE: usize = 1000;
+
+/// Header hashes returned unless the caller asks otherwise
+const DEFAULT_HEADERS_LIMIT: u64 = 100;
 
-/// Health check endpoint
-pub async fn health_check() -> StatusCode {
-    StatusCode::OK
+/// Upper bound on header hashes in one request
+const MAX_HEADERS_LIMIT: u64 = 1000;
+
+/// Node calls gathered by `/rpc/status`
+const STATUS_SECTIONS: usize = 5;
+
+/// DAA score distance before a coinbase output is spendable (10 BPS networks)
+const DEFAULT_COINBASE_MATURITY: u64 = 1000;
+
+/// Health check endpoint with per-upstream status
+///
+/// Always answers 200 while the service runs; unreachable nodes only
+/// degrade the reported status.
+pub async fn health_check(State(state): State<AppState>) -> Json<HealthResponse> {
+    let upstreams: Vec<UpstreamStatus> = state
+        .upstreams
+        .upstreams()
+        .iter()
+        .map(|upstream| UpstreamStatus {
+            url: upstream.url().to_string(),
+            health: upstream.health(),
+        })
+        .collect();
+
+    let status = if upstreams.iter().all(|u| u.health.healthy) {
+        "ok"
+    } else {
+        "degraded"
+    };
+
+    Json(HealthResponse {
+        status: status.to_string(),
+        upstreams,
+    })
 }
 
 /// Metrics endpoint (Prometheus format)
@@ -16,106 +89,106 @@ pub async fn metrics() -> String {
     metrics::export_metrics()
 }
 
+/// Exchange an API key for a JWT carrying the key's role
+pub async fn issue_token(
+    State(state): State<AppState>,
+    Json(request): Json<TokenRequest>,
+) -> Result<Json<RpcResponse<TokenResponse>>, RpcError> {
+    let start = Instant::now();
+
+    let (subject, role) = state.api_keys.verify(&request.api_key).await?;
+    let token = auth::generate_token(&subject, &state.jwt_secret, &role)?;
+
+    // bcrypt is slow by design, so these stay out of the latency metrics
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    Ok(Json(RpcResponse::success(
+        TokenResponse {
+            token,
+            role,
+            expires_in: auth::TOKEN_LIFETIME_SECS,
+        },
+        latency_ms,
+    )))
+}
+
+/// Create an API key (admin only)
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<Json<RpcResponse<CreatedApiKey>>, RpcError> {
+    let start = Instant::now();
+
+    let name = request.name.trim().to_string();
+    if name.is_empty() {
+        return Err(RpcError::BadRequest("API key name is required".into()));
+    }
+    if !auth::ROLES.contains(&request.role.as_str()) {
+        return Err(RpcError::BadRequest(format!(
+            "Unknown role '{}', expected one of: {}",
+            request.role,
+            auth::ROLES.join(", ")
+        )));
+    }
+
+    let (record, api_key) = state.api_keys.create(name, request.role).await?;
+    tracing::info!(key = %record.id, role = %record.role, "Created API key");
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    Ok(Json(RpcResponse::success(
+        CreatedApiKey {
+            info: record.into(),
+            api_key,
+        },
+        latency_ms,
+    )))
+}
+
+/// List API keys (admin only)
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+) -> Result<Json<RpcResponse<Vec<ApiKeyInfo>>>, RpcError> {
+    let start = Instant::now();
+
+    let keys = state.api_keys.list().into_iter().map(Into::into).collect();
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    Ok(Json(RpcResponse::success(keys, latency_ms)))
+}
+
+/// Revoke an API key and every token issued for it (admin only)
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> Result<Json<RpcResponse<ApiKeyInfo>>, RpcError> {
+    let start = Instant::now();
+
+    let record = state.api_keys.revoke(&id).await?;
+    tracing::info!(key = %record.id, "Revoked API key");
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+    Ok(Json(RpcResponse::success(record.into(), latency_ms)))
+}
+
 /// Get block by hash
 pub async fn get_block(
     State(state): State<AppState>,
-    // Optional JWT auth middleware can be added here
     Json(request): Json<GetBlockRequest>,
 ) -> Result<Json<RpcResponse<BlockResponse>>, RpcError> {
     let start = Instant::now();
     
-    // Validate hash format
-    if !is_valid_hash(&request.hash) {
-        return Err(RpcError::BadRequest("Invalid block hash format".into()));
-    }
+    validate_block_hash(&request.hash)?;
 
-    // Call Kaspa node
-    let response = state
-        .kaspa_client
-        .get_block(request.hash.clone(), request.include_transactions)
-        .await?;
+    // Served from cache, or from a call shared with identical requests
+    let block = fetch_block(&state, &request.hash, request.include_transactions).await?;
 
     let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
     metrics::record_latency("get_block", latency_ms);
 
     // Convert proto response to JSON model
-    let block = response.block.ok_or_else(|| {
-        RpcError::InvalidResponse("Block data missing".into())
-    })?;
-
-    let header = block.header.as_ref().ok_or_else(|| {
-        RpcError::InvalidResponse("Block header missing".into())
-    })?;
-
-    let block_response = BlockResponse {
-        hash: header.hash.clone(),
-        header: BlockHeader {
-            version: header.version,
-            hash_merkle_root: header.hash_merkle_root.clone(),
-            accepted_id_merkle_root: header.accepted_id_merkle_root.clone(),
-            utxo_commitment: header.utxo_commitment.clone(),
-            timestamp: header.timestamp,
-            bits: header.bits,
-            nonce: header.nonce,
-            daa_score: header.daa_score,
-            blue_work: header.blue_work.clone(),
-            blue_score: header.blue_score,
-            pruning_point: header.pruning_point.clone(),
-        },
-        transactions: block
-            .transactions
-            .iter()
-            .map(|tx| {
-                let verbose = tx.verbose_data.as_ref();
-                Transaction {
-                    transaction_id: verbose
-                        .map(|v| v.transaction_id.clone())
-                        .unwrap_or_default(),
-                    hash: verbose.map(|v| v.hash.clone()).unwrap_or_default(),
-                    mass: tx.mass,
-                    inputs: tx
-                        .inputs
-                        .iter()
-                        .map(|input| {
-                            let outpoint = input.previous_outpoint.as_ref();
-                            TransactionInputVerbose {
-                                previous_outpoint: OutpointVerbose {
-                                    transaction_id: outpoint
-                                        .map(|o| o.transaction_id.clone())
-                                        .unwrap_or_default(),
-                                    index: outpoint.map(|o| o.index).unwrap_or(0),
-                                },
-                                signature_script: input.signature_script.clone(),
-                                sequence: input.sequence,
-                            }
-                        })
-                        .collect(),
-                    outputs: tx
-                        .outputs
-                        .iter()
-                        .map(|output| {
-                            let script_pk = output.script_public_key.as_ref();
-                            TransactionOutput {
-                                amount: output.amount,
-                                script_public_key: script_pk
-                                    .map(|s| s.script_public_key.clone())
-                                    .unwrap_or_default(),
-                            }
-                        })
-                        .collect(),
-                }
-            })
-            .collect(),
-        verbose_data: block.verbose_data.as_ref().map(|vd| BlockVerboseData {
-            hash: vd.hash.clone(),
-            difficulty: vd.difficulty,
-            selected_parent_hash: vd.selected_parent_hash.clone(),
-            transaction_ids: vd.transaction_ids.clone(),
-            is_header_only: vd.is_header_only,
-            blue_score: vd.blue_score,
-            is_chain_block: vd.is_chain_block,
-        }),
-    };
+    let block_response = BlockResponse::try_from(block.as_ref())?;
 
     Ok(Json(RpcResponse::success(block_response, latency_ms)))
 }
@@ -127,32 +200,342 @@ pub async fn submit_transaction(
 ) -> Result<Json<RpcResponse<SubmitTransactionResponse>>, RpcError> {
     let start = Instant::now();
 
-    // Convert JSON transaction to proto format
-    let proto_tx = convert_to_proto_transaction(request.transaction)?;
+    // Reject malformed fields before anything reaches the node
+    let tx = validate_transaction(request.transaction)?;
+    let transaction_id = tx.id();
+    let transaction_hash = tx.hash();
+    let estimated_mass = tx.estimated_mass();
+
+    if request.dry_run {
+        let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+        return Ok(Json(RpcResponse::success(
+            SubmitTransactionResponse {
+                transaction_id,
+                transaction_hash,
+                estimated_mass,
+                dry_run: true,
+            },
+            latency_ms,
+        )));
+    }
 
     // Submit to Kaspa node
+    let proto_tx = tx.to_proto();
     let response = state
-        .kaspa_client
-        .submit_transaction(proto_tx, request.allow_orphan)
+        .upstreams
+        .call(|client| {
+            Box::pin(client.submit_transaction(proto_tx.clone(), request.allow_orphan))
+        })
         .await?;
 
     let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
     metrics::record_latency("submit_transaction", latency_ms);
 
+    if response.transaction_id != transaction_id {
+        tracing::warn!(
+            "Node accepted transaction {} but it was computed as {}",
+            response.transaction_id,
+            transaction_id
+        );
+    }
+
     let submit_response = SubmitTransactionResponse {
         transaction_id: response.transaction_id,
+        transaction_hash,
+        estimated_mass,
+        dry_run: false,
     };
 
     Ok(Json(RpcResponse::success(submit_response, latency_ms)))
 }
 
+/// Replace a mempool transaction with one spending the same outputs
+///
+/// The node only accepts the replacement if it pays a higher fee rate
+/// than the transaction it evicts.
+pub async fn submit_transaction_replacement(
+    State(state): State<AppState>,
+    Json(request): Json<SubmitTransactionReplacementRequest>,
+) -> Result<Json<RpcResponse<SubmitTransactionReplacementResponse>>, RpcError> {
+    let start = Instant::now();
+
+    let proto_tx = validate_transaction(request.transaction)?.to_proto();
+
+    let response = state
+        .upstreams
+        .call(|client| Box::pin(client.submit_transaction_replacement(proto_tx.clone())))
+        .await?;
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("submit_transaction_replacement", latency_ms);
+
+    let replaced = response.replaced_transaction.ok_or_else(|| {
+        RpcError::InvalidResponse("Replaced transaction missing".into())
+    })?;
+    let replaced_transaction = Transaction::from(&replaced);
+
+    // The node may leave out verbose data, so compute the ID when it does
+    let replaced_transaction_id = Some(replaced_transaction.transaction_id.clone())
+        .filter(|id| !id.is_empty())
+        .or_else(|| {
+            ValidatedTransaction::validate(&replaced, "replacedTransaction")
+                .ok()
+                .map(|tx| tx.id())
+        });
+
+    let replacement_response = SubmitTransactionReplacementResponse {
+        transaction_id: response.transaction_id,
+        replaced_transaction_id,
+        replaced_transaction,
+    };
+
+    Ok(Json(RpcResponse::success(replacement_response, latency_ms)))
+}
+
+/// Get many blocks by hash
+///
+/// Fetches run concurrently, at most `BLOCK_BATCH_CONCURRENCY` at a time.
+/// A block that cannot be fetched carries its error instead of failing
+/// the whole batch; results follow the request order.
+pub async fn get_blocks(
+    State(state): State<AppState>,
+    Json(request): Json<GetBlocksRequest>,
+) -> Result<Json<RpcResponse<BlocksResponse>>, RpcError> {
+    let start = Instant::now();
+
+    if request.hashes.is_empty() {
+        return Err(RpcError::BadRequest("No hashes provided".into()));
+    }
+    if request.hashes.len() > MAX_BLOCK_BATCH {
+        return Err(RpcError::BadRequest(format!(
+            "At most {} hashes per request",
+            MAX_BLOCK_BATCH
+        )));
+    }
+    if let Some(hash) = request.hashes.iter().find(|hash| !is_valid_hash(hash)) {
+        return Err(RpcError::BadRequest(format!(
+            "Invalid block hash format: {}",
+            hash
+        )));
+    }
+
+    let mut results = fetch_blocks(&state, &request.hashes, request.include_transactions).await?;
+
+    // Without a node that answers the batch as a whole has failed
+    if results.iter().all(|result| {
+        matches!(result, Err(RpcError::Connection(_) | RpcError::Timeout(_)))
+    }) {
+        results.swap_remove(0)?;
+    }
+
+    let blocks = request
+        .hashes
+        .into_iter()
+        .zip(results)
+        .map(
+            |(hash, result)| match result.and_then(|block| BlockResponse::try_from(block.as_ref())) {
+                Ok(block) => BlockResult {
+                    hash,
+                    block: Some(block),
+                    error: None,
+                },
+                Err(e) => BlockResult {
+                    hash,
+                    block: None,
+                    error: Some(e.into()),
+                },
+            },
+        )
+        .collect();
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_blocks", latency_ms);
+
+    Ok(Json(RpcResponse::success(BlocksResponse { blocks }, latency_ms)))
+}
+
+/// Page through the DAG from a low hash towards the virtual
+///
+/// The cursor is `<anchor>:<offset>`: the blocks the node lists above the
+/// anchor, of which the first `offset` were already delivered. Merged
+/// blocks can show up once per chain block that merges them, so each
+/// listing is de-duplicated before paging. The cursor moves to the last
+/// chain block of a page, keeping the listing above the anchor short.
+/// The listing comes with headers and verbose data, which tell chain
+/// blocks apart; transactions are only fetched for the page.
+pub async fn get_block_range(
+    State(state): State<AppState>,
+    Json(request): Json<GetBlockRangeRequest>,
+) -> Result<Json<RpcResponse<BlockRangeResponse>>, RpcError> {
+    let start = Instant::now();
+
+    let limit = request.limit.unwrap_or(DEFAULT_BLOCK_RANGE_PAGE_SIZE);
+    if limit == 0 || limit > MAX_BLOCK_RANGE_PAGE_SIZE {
+        return Err(RpcError::BadRequest(format!(
+            "Limit must be between 1 and {}",
+            MAX_BLOCK_RANGE_PAGE_SIZE
+        )));
+    }
+    let (anchor, offset) = match request.cursor {
+        Some(cursor) => parse_block_cursor(&cursor)?,
+        None => (request.low_hash, 0),
+    };
+    if !anchor.is_empty() && !is_valid_hash(&anchor) {
+        return Err(RpcError::BadRequest("Invalid low hash format".into()));
+    }
+
+    let response = state
+        .upstreams
+        .call(|client| Box::pin(client.get_blocks(anchor.clone(), true, false)))
+        .await?;
+    if response.blocks.len() != response.block_hashes.len() {
+        return Err(RpcError::InvalidResponse(
+            "Blocks do not match their hashes".into(),
+        ));
+    }
+
+    let listing: Vec<(&str, bool)> = response
+        .block_hashes
+        .iter()
+        .zip(&response.blocks)
+        .map(|(hash, block)| {
+            let is_chain_block = block.verbose_data.as_ref().is_some_and(|vd| vd.is_chain_block);
+            (hash.as_str(), is_chain_block)
+        })
+        .collect();
+    let (page, next_cursor) = block_range_page(&anchor, offset, limit, &listing);
+    let block_hashes: Vec<String> = page
+        .iter()
+        .map(|&index| response.block_hashes[index].clone())
+        .collect();
+
+    let blocks = match (request.include_blocks, request.include_transactions) {
+        (false, _) => None,
+        (true, false) => Some(
+            page.iter()
+                .map(|&index| BlockResponse::try_from(&response.blocks[index]))
+                .collect::<Result<Vec<_>, RpcError>>()?,
+        ),
+        (true, true) => Some(
+            fetch_blocks(&state, &block_hashes, true)
+                .await?
+                .into_iter()
+                .map(|block| block.and_then(|block| BlockResponse::try_from(block.as_ref())))
+                .collect::<Result<Vec<_>, RpcError>>()?,
+        ),
+    };
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_block_range", latency_ms);
+
+    Ok(Json(RpcResponse::success(
+        BlockRangeResponse {
+            block_hashes,
+            blocks,
+            next_cursor,
+        },
+        latency_ms,
+    )))
+}
+
+/// Get header hashes from a start hash, ascending or descending
+pub async fn get_headers(
+    State(state): State<AppState>,
+    Json(request): Json<GetHeadersRequest>,
+) -> Result<Json<RpcResponse<HeadersResponse>>, RpcError> {
+    let start = Instant::now();
+
+    if !is_valid_hash(&request.start_hash) {
+        return Err(RpcError::BadRequest("Invalid start hash format".into()));
+    }
+    let limit = request.limit.unwrap_or(DEFAULT_HEADERS_LIMIT);
+    if limit == 0 || limit > MAX_HEADERS_LIMIT {
+        return Err(RpcError::BadRequest(format!(
+            "Limit must be between 1 and {}",
+            MAX_HEADERS_LIMIT
+        )));
+    }
+
+    let response = state
+        .upstreams
+        .call(|client| {
+            Box::pin(client.get_headers(
+                request.start_hash.clone(),
+                limit,
+                request.is_ascending,
+            ))
+        })
+        .await?;
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_headers", latency_ms);
+
+    Ok(Json(RpcResponse::success(
+        HeadersResponse {
+            headers: response.headers,
+        },
+        latency_ms,
+    )))
+}
+
+/// Get the selected chain changes from a block up to the virtual
+///
+/// `removedChainBlockHashes` lists chain blocks reorged out since the
+/// start (high to low), to be rolled back before applying the added ones
+/// (low to high). The node caps how many added blocks one call returns;
+/// polling with `nextCursor` picks up the rest and every later change.
+pub async fn get_virtual_chain_from_block(
+    State(state): State<AppState>,
+    Json(request): Json<GetVirtualChainFromBlockRequest>,
+) -> Result<Json<RpcResponse<VirtualChainFromBlockResponse>>, RpcError> {
+    let start = Instant::now();
+
+    let start_hash = request.cursor.unwrap_or(request.start_hash);
+    if !is_valid_hash(&start_hash) {
+        return Err(RpcError::BadRequest("Invalid start hash format".into()));
+    }
+
+    let response = state
+        .upstreams
+        .call(|client| {
+            Box::pin(client.get_virtual_chain_from_block(
+                start_hash.clone(),
+                request.include_accepted_transaction_ids,
+                request.min_confirmation_count,
+            ))
+        })
+        .await?;
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_virtual_chain_from_block", latency_ms);
+
+    // With nothing added the start is still the best place to resume;
+    // any removals it reported will be reported again
+    let next_cursor = response
+        .added_chain_block_hashes
+        .last()
+        .cloned()
+        .unwrap_or(start_hash);
+
+    Ok(Json(RpcResponse::success(
+        VirtualChainFromBlockResponse {
+            chain: response.into(),
+            next_cursor,
+        },
+        latency_ms,
+    )))
+}
+
 /// Get DAG tips (virtual selected parent chain)
 pub async fn get_dag_tips(
     State(state): State<AppState>,
 ) -> Result<Json<RpcResponse<DAGTipsResponse>>, RpcError> {
     let start = Instant::now();
 
-    let response = state.kaspa_client.get_dag_tips().await?;
+    let response = state
+        .upstreams
+        .call(|client| Box::pin(client.get_dag_tips()))
+        .await?;
 
     let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
     metrics::record_latency("get_dag_tips", latency_ms);
@@ -171,18 +554,659 @@ pub async fn get_dag_tips(
     Ok(Json(RpcResponse::success(dag_response, latency_ms)))
 }
 
+/// Get the UTXOs of a set of addresses, filtered and paginated
+///
+/// Entries are ordered by address, then outpoint, so a cursor stays
+/// valid while the UTXO set changes underneath it.
+pub async fn get_utxos_by_addresses(
+    State(state): State<AppState>,
+    Json(request): Json<GetUtxosByAddressesRequest>,
+) -> Result<Json<RpcResponse<UtxosResponse>>, RpcError> {
+    let start = Instant::now();
+
+    validate_addresses("addresses", &request.addresses, MAX_UTXO_ADDRESSES)?;
+    let limit = request.limit.unwrap_or(DEFAULT_UTXO_PAGE_SIZE);
+    if limit == 0 || limit > MAX_UTXO_PAGE_SIZE {
+        return Err(RpcError::BadRequest(format!(
+            "Limit must be between 1 and {}",
+            MAX_UTXO_PAGE_SIZE
+        )));
+    }
+    let after = request.cursor.as_deref().map(decode_utxo_cursor).transpose()?;
+    let coinbase_maturity = request.coinbase_maturity.unwrap_or(DEFAULT_COINBASE_MATURITY);
+
+    // Both from the same node, so maturity is judged against the DAA
+    // score of the state the UTXOs were read from
+    let (utxos, dag_info) = state
+        .upstreams
+        .call(|client| {
+            let addresses = request.addresses.clone();
+            Box::pin(async move {
+                tokio::try_join!(
+                    client.get_utxos_by_addresses(addresses),
+                    client.get_dag_tips()
+                )
+            })
+        })
+        .await?;
+    let virtual_daa_score = dag_info.virtual_daa_score;
+
+    let mut entries: Vec<UtxoResponse> = utxos
+        .entries
+        .into_iter()
+        .filter_map(|entry| {
+            let outpoint = entry.outpoint?;
+            let utxo = entry.utxo_entry?;
+            let is_mature = !utxo.is_coinbase
+                || utxo.block_daa_score.saturating_add(coinbase_maturity) <= virtual_daa_score;
+            let script = utxo.script_public_key.unwrap_or_default();
+
+            Some(UtxoResponse {
+                address: entry.address,
+                outpoint: OutpointVerbose {
+                    transaction_id: outpoint.transaction_id,
+                    index: outpoint.index,
+                },
+                amount: utxo.amount,
+                script_public_key: script.script_public_key,
+                script_version: script.version,
+                block_daa_score: utxo.block_daa_score,
+                is_coinbase: utxo.is_coinbase,
+                is_mature,
+            })
+        })
+        .filter(|utxo| utxo.amount >= request.min_amount)
+        .filter(|utxo| utxo.is_mature || !request.mature_only)
+        .filter(|utxo| match &after {
+            Some(after) => utxo_sort_key(utxo) > (after.0.as_str(), after.1.as_str(), after.2),
+            None => true,
+        })
+        .collect();
+
+    entries.sort_by(|a, b| utxo_sort_key(a).cmp(&utxo_sort_key(b)));
+
+    let next_cursor = if entries.len() > limit {
+        entries.truncate(limit);
+        entries.last().map(encode_utxo_cursor)
+    } else {
+        None
+    };
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_utxos_by_addresses", latency_ms);
+
+    Ok(Json(RpcResponse::success(
+        UtxosResponse {
+            utxos: entries,
+            next_cursor,
+            virtual_daa_score,
+        },
+        latency_ms,
+    )))
+}
+
+/// Get the balance of one address, served from cache when fresh
+pub async fn get_balance(
+    State(state): State<AppState>,
+    Json(request): Json<GetBalanceRequest>,
+) -> Result<Json<RpcResponse<BalanceResponse>>, RpcError> {
+    let start = Instant::now();
+
+    if request.address.is_empty() {
+        return Err(RpcError::BadRequest("No address provided".into()));
+    }
+    address::validate_one("address", &request.address)?;
+
+    let balance = fetch_balance(&state, &request.address).await?;
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_balance", latency_ms);
+
+    Ok(Json(RpcResponse::success(
+        BalanceResponse::new(request.address, balance),
+        latency_ms,
+    )))
+}
+
+/// Get the balances of several addresses in one call
+///
+/// Cached balances are served directly; the rest are fetched from the
+/// node in a single request. Results follow the request order.
+pub async fn get_balances(
+    State(state): State<AppState>,
+    Json(request): Json<GetBalancesRequest>,
+) -> Result<Json<RpcResponse<BalancesResponse>>, RpcError> {
+    let start = Instant::now();
+
+    validate_addresses("addresses", &request.addresses, MAX_BALANCE_ADDRESSES)?;
+
+    let balances = fetch_balances(&state, &request.addresses)
+        .await?
+        .into_iter()
+        .zip(request.addresses)
+        .map(|(balance, address)| BalanceResponse::new(address, balance))
+        .collect();
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_balances", latency_ms);
+
+    Ok(Json(RpcResponse::success(
+        BalancesResponse { balances },
+        latency_ms,
+    )))
+}
+
+/// Get one mempool transaction by ID
+pub async fn get_mempool_entry(
+    State(state): State<AppState>,
+    Json(request): Json<GetMempoolEntryRequest>,
+) -> Result<Json<RpcResponse<MempoolEntryResponse>>, RpcError> {
+    let start = Instant::now();
+
+    if !is_valid_hash(&request.transaction_id) {
+        return Err(RpcError::BadRequest("Invalid transaction ID format".into()));
+    }
+
+    let response = state
+        .upstreams
+        .call(|client| {
+            Box::pin(client.get_mempool_entry(
+                request.transaction_id.clone(),
+                request.include_orphan_pool,
+                request.filter_transaction_pool,
+            ))
+        })
+        .await?;
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_mempool_entry", latency_ms);
+
+    let entry = response.entry.ok_or_else(|| {
+        RpcError::NotFound(format!(
+            "Transaction {} not found in the mempool",
+            request.transaction_id
+        ))
+    })?;
+
+    Ok(Json(RpcResponse::success(
+        MempoolEntryResponse::try_from(&entry)?,
+        latency_ms,
+    )))
+}
+
+/// Get a page of the mempool, sorted by fee rate or transaction ID
+///
+/// The node returns the whole mempool in one message; the proxy sorts
+/// it and cuts pages keyed by the last entry, so entries entering or
+/// leaving the mempool do not shift later pages.
+pub async fn get_mempool_entries(
+    State(state): State<AppState>,
+    Json(request): Json<GetMempoolEntriesRequest>,
+) -> Result<Json<RpcResponse<MempoolEntriesResponse>>, RpcError> {
+    let start = Instant::now();
+
+    let limit = request.limit.unwrap_or(DEFAULT_MEMPOOL_PAGE_SIZE);
+    if limit == 0 || limit > MAX_MEMPOOL_PAGE_SIZE {
+        return Err(RpcError::BadRequest(format!(
+            "Limit must be between 1 and {}",
+            MAX_MEMPOOL_PAGE_SIZE
+        )));
+    }
+    let after = request
+        .cursor
+        .as_deref()
+        .map(decode_mempool_cursor)
+        .transpose()?;
+
+    let response = state
+        .upstreams
+        .call(|client| {
+            Box::pin(client.get_mempool_entries(
+                request.include_orphan_pool,
+                request.filter_transaction_pool,
+            ))
+        })
+        .await?;
+
+    let mut entries = response
+        .entries
+        .iter()
+        .map(MempoolEntryResponse::try_from)
+        .collect::<Result<Vec<_>, RpcError>>()?;
+    let total = entries.len();
+
+    sort_mempool_entries(&mut entries, request.sort);
+    if let Some((fee_rate, transaction_id)) = &after {
+        let after = (*fee_rate, transaction_id.as_str());
+        entries.retain(|entry| {
+            compare_mempool_keys(mempool_sort_key(entry), after, request.sort).is_gt()
+        });
+    }
+
+    let next_cursor = if entries.len() > limit {
+        entries.truncate(limit);
+        entries.last().map(encode_mempool_cursor)
+    } else {
+        None
+    };
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_mempool_entries", latency_ms);
+
+    Ok(Json(RpcResponse::success(
+        MempoolEntriesResponse {
+            entries,
+            next_cursor,
+            total,
+        },
+        latency_ms,
+    )))
+}
+
+/// Get the mempool transactions spending from or paying to a set of addresses
+pub async fn get_mempool_entries_by_addresses(
+    State(state): State<AppState>,
+    Json(request): Json<GetMempoolEntriesByAddressesRequest>,
+) -> Result<Json<RpcResponse<MempoolEntriesByAddressesResponse>>, RpcError> {
+    let start = Instant::now();
+
+    validate_addresses("addresses", &request.addresses, MAX_MEMPOOL_ADDRESSES)?;
+
+    let response = state
+        .upstreams
+        .call(|client| {
+            Box::pin(client.get_mempool_entries_by_addresses(
+                request.addresses.clone(),
+                request.include_orphan_pool,
+                request.filter_transaction_pool,
+            ))
+        })
+        .await?;
+
+    let convert = |entries: &[client::proto::RpcMempoolEntry]| {
+        let mut entries = entries
+            .iter()
+            .map(MempoolEntryResponse::try_from)
+            .collect::<Result<Vec<_>, RpcError>>()?;
+        sort_mempool_entries(&mut entries, request.sort);
+        Ok::<_, RpcError>(entries)
+    };
+
+    let entries = response
+        .entries
+        .iter()
+        .map(|entry| {
+            Ok(MempoolEntriesByAddressResponse {
+                address: entry.address.clone(),
+                sending: convert(&entry.sending)?,
+                receiving: convert(&entry.receiving)?,
+            })
+        })
+        .collect::<Result<Vec<_>, RpcError>>()?;
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_mempool_entries_by_addresses", latency_ms);
+
+    Ok(Json(RpcResponse::success(
+        MempoolEntriesByAddressesResponse { entries },
+        latency_ms,
+    )))
+}
+
+/// Get fee rate estimates per priority tier
+///
+/// With `mass` every tier also carries the total fee for a transaction
+/// of that mass. `verbose` switches to the node's experimental call,
+/// which adds the mempool data the estimate was derived from.
+pub async fn get_fee_estimate(
+    State(state): State<AppState>,
+    Json(request): Json<GetFeeEstimateRequest>,
+) -> Result<Json<RpcResponse<FeeEstimateResponse>>, RpcError> {
+    let start = Instant::now();
+
+    if request.mass == Some(0) {
+        return Err(RpcError::BadRequest("Mass must be positive".into()));
+    }
+
+    let (estimate, verbose) = if request.verbose {
+        let response = state
+            .upstreams
+            .call(|client| Box::pin(client.get_fee_estimate_experimental(true)))
+            .await?;
+        (response.estimate, response.verbose)
+    } else {
+        let response = state
+            .upstreams
+            .call(|client| Box::pin(client.get_fee_estimate()))
+            .await?;
+        (response.estimate, None)
+    };
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_fee_estimate", latency_ms);
+
+    let estimate = estimate.ok_or_else(|| {
+        RpcError::InvalidResponse("Fee estimate missing".into())
+    })?;
+
+    let mut fee_estimate = FeeEstimateResponse::new(&estimate, request.mass);
+    fee_estimate.verbose = verbose.as_ref().map(FeeEstimateVerbose::from);
+
+    Ok(Json(RpcResponse::success(fee_estimate, latency_ms)))
+}
+
+/// Get node version, mempool size and capabilities
+pub async fn get_info(
+    State(state): State<AppState>,
+) -> Result<Json<RpcResponse<InfoResponse>>, RpcError> {
+    let start = Instant::now();
+
+    let response = state
+        .upstreams
+        .call(|client| Box::pin(client.get_info()))
+        .await?;
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_info", latency_ms);
+
+    Ok(Json(RpcResponse::success(response.into(), latency_ms)))
+}
+
+/// Get RPC API version, network and sync state
+pub async fn get_server_info(
+    State(state): State<AppState>,
+) -> Result<Json<RpcResponse<ServerInfoResponse>>, RpcError> {
+    let start = Instant::now();
+
+    let response = state
+        .upstreams
+        .call(|client| Box::pin(client.get_server_info()))
+        .await?;
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_server_info", latency_ms);
+
+    Ok(Json(RpcResponse::success(response.into(), latency_ms)))
+}
+
+/// Get whether the node is synced
+pub async fn get_sync_status(
+    State(state): State<AppState>,
+) -> Result<Json<RpcResponse<SyncStatusResponse>>, RpcError> {
+    let start = Instant::now();
+
+    let response = state
+        .upstreams
+        .call(|client| Box::pin(client.get_sync_status()))
+        .await?;
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_sync_status", latency_ms);
+
+    Ok(Json(RpcResponse::success(response.into(), latency_ms)))
+}
+
+/// Get the network the node runs on
+pub async fn get_current_network(
+    State(state): State<AppState>,
+) -> Result<Json<RpcResponse<CurrentNetworkResponse>>, RpcError> {
+    let start = Instant::now();
+
+    let response = state
+        .upstreams
+        .call(|client| Box::pin(client.get_current_network()))
+        .await?;
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_current_network", latency_ms);
+
+    Ok(Json(RpcResponse::success(response.into(), latency_ms)))
+}
+
+/// Get the maximum and circulating coin supply
+pub async fn get_coin_supply(
+    State(state): State<AppState>,
+) -> Result<Json<RpcResponse<CoinSupplyResponse>>, RpcError> {
+    let start = Instant::now();
+
+    let response = state
+        .upstreams
+        .call(|client| Box::pin(client.get_coin_supply()))
+        .await?;
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("get_coin_supply", latency_ms);
+
+    Ok(Json(RpcResponse::success(response.into(), latency_ms)))
+}
+
+/// Gather node info, server info, sync status, network and coin supply
+///
+/// The calls run concurrently. A failed call leaves its section out and
+/// adds its error instead; the request only fails when every call does.
+pub async fn status(
+    State(state): State<AppState>,
+) -> Result<Json<RpcResponse<StatusResponse>>, RpcError> {
+    let start = Instant::now();
+
+    let (info, server_info, sync_status, network, coin_supply) = tokio::join!(
+        state.upstreams.call(|client| Box::pin(client.get_info())),
+        state.upstreams.call(|client| Box::pin(client.get_server_info())),
+        state.upstreams.call(|client| Box::pin(client.get_sync_status())),
+        state.upstreams.call(|client| Box::pin(client.get_current_network())),
+        state.upstreams.call(|client| Box::pin(client.get_coin_supply())),
+    );
+
+    let mut errors = BTreeMap::new();
+    let mut first_error = None;
+    let status = StatusResponse {
+        info: status_section("info", info, &mut errors, &mut first_error),
+        server_info: status_section("serverInfo", server_info, &mut errors, &mut first_error),
+        sync_status: status_section("syncStatus", sync_status, &mut errors, &mut first_error),
+        network: status_section("network", network, &mut errors, &mut first_error),
+        coin_supply: status_section("coinSupply", coin_supply, &mut errors, &mut first_error),
+        errors,
+    };
+
+    let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+    metrics::record_latency("status", latency_ms);
+
+    if status.errors.len() == STATUS_SECTIONS {
+        if let Some(e) = first_error {
+            return Err(e);
+        }
+    }
+
+    Ok(Json(RpcResponse::success(status, latency_ms)))
+}
+
+/// Helper: Fetch a block through the block cache
+pub async fn fetch_block(
+    state: &AppState,
+    hash: &str,
+    include_transactions: bool,
+) -> Result<Arc<client::proto::RpcBlock>, RpcError> {
+    state
+        .blocks
+        .get_or_fetch(hash, include_transactions, || async {
+            let response = state
+                .upstreams
+                .call(|client| Box::pin(client.get_block(hash.to_string(), include_transactions)))
+                .await?;
+            response
+                .block
+                .ok_or_else(|| RpcError::InvalidResponse("Block data missing".into()))
+        })
+        .await
+}
+
+/// Helper: Fetch blocks concurrently, `BLOCK_BATCH_CONCURRENCY` at a time,
+/// keeping the order of `hashes`
+async fn fetch_blocks(
+    state: &AppState,
+    hashes: &[String],
+    include_transactions: bool,
+) -> Result<Vec<Result<Arc<client::proto::RpcBlock>, RpcError>>, RpcError> {
+    let permits = Arc::new(Semaphore::new(BLOCK_BATCH_CONCURRENCY));
+    let mut tasks = JoinSet::new();
+    for (index, hash) in hashes.iter().cloned().enumerate() {
+        let state = state.clone();
+        let permits = permits.clone();
+        tasks.spawn(async move {
+            let _permit = permits.acquire_owned().await;
+            (index, fetch_block(&state, &hash, include_transactions).await)
+        });
+    }
+
+    let mut results: Vec<Option<Result<Arc<client::proto::RpcBlock>, RpcError>>> =
+        hashes.iter().map(|_| None).collect();
+    while let Some(joined) = tasks.join_next().await {
+        let (index, result) = joined.map_err(|e| RpcError::Internal(e.to_string()))?;
+        results[index] = Some(result);
+    }
+
+    Ok(results
+        .into_iter()
+        .map(|result| {
+            result.unwrap_or_else(|| Err(RpcError::Internal("Block fetch did not complete".into())))
+        })
+        .collect())
+}
+
+/// Helper: Fetch the balance of one address through the balance cache
+pub async fn fetch_balance(state: &AppState, address: &str) -> Result<u64, RpcError> {
+    if let Some(balance) = state.balances.get(address) {
+        return Ok(balance);
+    }
+
+    let fetch_started = Instant::now();
+    let response = state
+        .upstreams
+        .call(|client| Box::pin(client.get_balance_by_address(address.to_string())))
+        .await?;
+    state
+        .balances
+        .insert(address.to_string(), response.balance, fetch_started);
+    Ok(response.balance)
+}
+
+/// Helper: Fetch balances through the balance cache, in the order of
+/// `addresses`
+pub async fn fetch_balances(state: &AppState, addresses: &[String]) -> Result<Vec<u64>, RpcError> {
+    let mut balances = HashMap::new();
+    let mut missing = Vec::new();
+    let mut seen = HashSet::new();
+    for address in addresses {
+        if !seen.insert(address.as_str()) {
+            continue;
+        }
+        match state.balances.get(address) {
+            Some(balance) => {
+                balances.insert(address.clone(), balance);
+            }
+            None => missing.push(address.clone()),
+        }
+    }
+
+    if !missing.is_empty() {
+        let fetch_started = Instant::now();
+        let response = state
+            .upstreams
+            .call(|client| Box::pin(client.get_balances_by_addresses(missing.clone())))
+            .await?;
+
+        for entry in response.entries {
+            if let Some(error) = entry.error {
+                return Err(RpcError::Kaspa(format!("{}: {}", entry.address, error.message)));
+            }
+            state
+                .balances
+                .insert(entry.address.clone(), entry.balance, fetch_started);
+            balances.insert(entry.address, entry.balance);
+        }
+    }
+
+    addresses
+        .iter()
+        .map(|address| {
+            balances.get(address).copied().ok_or_else(|| {
+                RpcError::InvalidResponse(format!("No balance returned for {}", address))
+            })
+        })
+        .collect()
+}
+
+/// Helper: Split a `getBlockRange` cursor into its anchor and offset
+fn parse_block_cursor(cursor: &str) -> Result<(String, usize), RpcError> {
+    cursor
+        .split_once(':')
+        .and_then(|(anchor, offset)| Some((anchor.to_string(), offset.parse().ok()?)))
+        .ok_or_else(|| RpcError::BadRequest("Invalid cursor".into()))
+}
+
+/// Helper: Pick one `getBlockRange` page out of the node's listing above
+/// `anchor`, given as hashes with whether each is a chain block
+///
+/// Returns the listing indices of the page, skipping the anchor and
+/// repeated hashes, and the cursor that follows the page.
+fn block_range_page(
+    anchor: &str,
+    offset: usize,
+    limit: usize,
+    listing: &[(&str, bool)],
+) -> (Vec<usize>, String) {
+    // The node lists the anchor itself, which an earlier page delivered
+    let mut seen = HashSet::from([anchor]);
+    let unique: Vec<usize> = (0..listing.len())
+        .filter(|&index| seen.insert(listing[index].0))
+        .collect();
+    let page: Vec<usize> = unique.into_iter().skip(offset).take(limit).collect();
+
+    // Re-anchor on the last chain block; what follows it was delivered too
+    let next_cursor = match page.iter().rposition(|&index| listing[index].1) {
+        Some(position) => format!(
+            "{}:{}",
+            listing[page[position]].0,
+            page.len() - position - 1
+        ),
+        None => format!("{}:{}", anchor, offset + page.len()),
+    };
+    (page, next_cursor)
+}
+
 /// Helper: Validate hash format (64 hex chars)
 fn is_valid_hash(hash: &str) -> bool {
     hash.len() == 64 && hash.chars().all(|c| c.is_ascii_hexdigit())
 }
 
-/// Helper: Convert JSON transaction to proto format
-fn convert_to_proto_transaction(
-    tx: TransactionInput,
-) -> Result<client::proto::RpcTransaction, RpcError> {
+/// Helper: Reject a block hash that is not 64 hex characters
+pub fn validate_block_hash(hash: &str) -> Result<(), RpcError> {
+    if !is_valid_hash(hash) {
+        return Err(RpcError::BadRequest("Invalid block hash format".into()));
+    }
+    Ok(())
+}
+
+/// Helper: Check an address list is non-empty, within `max` and on the
+/// service's network
+pub fn validate_addresses(field: &str, addresses: &[String], max: usize) -> Result<(), RpcError> {
+    if addresses.is_empty() {
+        return Err(RpcError::BadRequest("No addresses provided".into()));
+    }
+    if addresses.len() > max {
+        return Err(RpcError::BadRequest(format!(
+            "At most {} addresses per request",
+            max
+        )));
+    }
+    address::validate(field, addresses)
+}
+
+/// Helper: Convert a JSON transaction and validate every field
+fn validate_transaction(tx: TransactionInput) -> Result<ValidatedTransaction, RpcError> {
     use client::proto::*;
 
-    Ok(RpcTransaction {
+    let tx = RpcTransaction {
         version: tx.version.unwrap_or(0),
         inputs: tx
             .inputs
@@ -211,10 +1235,189 @@ fn convert_to_proto_transaction(
             })
             .collect(),
         lock_time: tx.lock_time.unwrap_or(0),
-        subnetwork_id: tx.subnetwork_id.unwrap_or_default(),
+        // Native subnetwork unless stated otherwise
+        subnetwork_id: tx.subnetwork_id.unwrap_or_else(|| "00".repeat(20)),
         gas: tx.gas.unwrap_or(0),
         payload: tx.payload.unwrap_or_default(),
-        mass: 0, // Will be calculated by node
+        mass: tx.mass.unwrap_or(0),
         verbose_data: None,
-    })
+    };
+
+    ValidatedTransaction::validate(&tx, "transaction")
+}
+
+/// Helper: Order UTXOs by address, then outpoint
+fn utxo_sort_key(utxo: &UtxoResponse) -> (&str, &str, u32) {
+    (
+        utxo.address.as_str(),
+        utxo.outpoint.transaction_id.as_str(),
+        utxo.outpoint.index,
+    )
+}
+
+/// Helper: Opaque cursor pointing just past `utxo`
+fn encode_utxo_cursor(utxo: &UtxoResponse) -> String {
+    hex::encode(format!(
+        "{}/{}/{}",
+        utxo.address, utxo.outpoint.transaction_id, utxo.outpoint.index
+    ))
+}
+
+/// Helper: Decode a cursor into the sort key it points past
+fn decode_utxo_cursor(cursor: &str) -> Result<(String, String, u32), RpcError> {
+    let invalid = || RpcError::BadRequest("Invalid cursor".into());
+
+    let decoded = hex::decode(cursor).map_err(|_| invalid())?;
+    let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+    let mut parts = decoded.rsplitn(3, '/');
+    let index = parts.next().and_then(|i| i.parse().ok()).ok_or_else(invalid)?;
+    let transaction_id = parts.next().ok_or_else(invalid)?;
+    let address = parts.next().ok_or_else(invalid)?;
+
+    Ok((address.to_string(), transaction_id.to_string(), index))
+}
+
+/// Helper: Fee rate and transaction ID, the keys mempool entries sort on
+fn mempool_sort_key(entry: &MempoolEntryResponse) -> (f64, &str) {
+    (entry.fee_rate, entry.transaction.transaction_id.as_str())
+}
+
+/// Helper: Order two mempool sort keys, ties broken by transaction ID
+fn compare_mempool_keys(a: (f64, &str), b: (f64, &str), sort: MempoolSort) -> Ordering {
+    let by_id = a.1.cmp(b.1);
+    match sort {
+        MempoolSort::FeeRate => b.0.total_cmp(&a.0).then(by_id),
+        MempoolSort::FeeRateAsc => a.0.total_cmp(&b.0).then(by_id),
+        MempoolSort::TransactionId => by_id,
+    }
+}
+
+/// Helper: Sort mempool entries in the requested order
+fn sort_mempool_entries(entries: &mut [MempoolEntryResponse], sort: MempoolSort) {
+    entries.sort_by(|a, b| compare_mempool_keys(mempool_sort_key(a), mempool_sort_key(b), sort));
+}
+
+/// Helper: Opaque cursor pointing just past `entry`
+fn encode_mempool_cursor(entry: &MempoolEntryResponse) -> String {
+    hex::encode(format!(
+        "{}/{}",
+        entry.fee_rate.to_bits(),
+        entry.transaction.transaction_id
+    ))
+}
+
+/// Helper: Decode a cursor into the sort key it points past
+fn decode_mempool_cursor(cursor: &str) -> Result<(f64, String), RpcError> {
+    let invalid = || RpcError::BadRequest("Invalid cursor".into());
+
+    let decoded = hex::decode(cursor).map_err(|_| invalid())?;
+    let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+    let (fee_rate, transaction_id) = decoded.split_once('/').ok_or_else(invalid)?;
+    let fee_rate = fee_rate.parse().map(f64::from_bits).map_err(|_| invalid())?;
+
+    Ok((fee_rate, transaction_id.to_string()))
+}
+
+/// Helper: Convert one `/rpc/status` section, recording its error instead on failure
+fn status_section<T, U: From<T>>(
+    name: &'static str,
+    result: Result<T, RpcError>,
+    errors: &mut BTreeMap<&'static str, String>,
+    first_error: &mut Option<RpcError>,
+) -> Option<U> {
+    match result {
+        Ok(section) => Some(section.into()),
+        Err(e) => {
+            tracing::warn!("Status section {} failed: {}", name, e);
+            errors.insert(name, e.to_string());
+            first_error.get_or_insert(e);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ANCHOR: &str = "anchor";
+
+    #[test]
+    fn parses_block_cursors() {
+        let hash = "ab".repeat(32);
+        let (anchor, offset) = parse_block_cursor(&format!("{}:7", hash)).unwrap();
+        assert_eq!((anchor.as_str(), offset), (hash.as_str(), 7));
+        // An empty anchor pages from the pruning point
+        assert_eq!(parse_block_cursor(":3").unwrap(), (String::new(), 3));
+    }
+
+    #[test]
+    fn rejects_malformed_block_cursors() {
+        let hash = "ab".repeat(32);
+        for cursor in [
+            hash.clone(),
+            format!("{}:", hash),
+            format!("{}:-1", hash),
+            format!("{}:x", hash),
+            format!("{}:1:2", hash),
+        ] {
+            assert!(
+                matches!(parse_block_cursor(&cursor), Err(RpcError::BadRequest(_))),
+                "{}",
+                cursor
+            );
+        }
+    }
+
+    #[test]
+    fn pages_skip_the_anchor_and_repeated_hashes() {
+        let listing = [
+            (ANCHOR, true),
+            ("a", false),
+            ("b", false),
+            ("a", false),
+            ("c", false),
+        ];
+        let (page, next_cursor) = block_range_page(ANCHOR, 0, 10, &listing);
+        assert_eq!(page, vec![1, 2, 4]);
+        // No chain block on the page, so the anchor stays
+        assert_eq!(next_cursor, "anchor:3");
+    }
+
+    #[test]
+    fn pages_reanchor_on_their_last_chain_block() {
+        let listing = [
+            (ANCHOR, true),
+            ("a", false),
+            ("b", true),
+            ("c", false),
+            ("d", true),
+            ("e", false),
+            ("f", false),
+        ];
+        let (page, next_cursor) = block_range_page(ANCHOR, 0, 5, &listing);
+        assert_eq!(page, vec![1, 2, 3, 4, 5]);
+        assert_eq!(next_cursor, "d:1");
+
+        // Paging on from "d" lists "d", "e", "f" and skips the delivered "e"
+        let listing = [("d", true), ("e", false), ("f", false), ("g", true)];
+        let (page, next_cursor) = block_range_page("d", 1, 5, &listing);
+        assert_eq!(page, vec![2, 3]);
+        assert_eq!(next_cursor, "g:0");
+    }
+
+    #[test]
+    fn pages_continue_past_the_offset() {
+        let listing = [(ANCHOR, true), ("a", false), ("b", false), ("c", false)];
+        let (page, next_cursor) = block_range_page(ANCHOR, 2, 10, &listing);
+        assert_eq!(page, vec![3]);
+        assert_eq!(next_cursor, "anchor:3");
+    }
+
+    #[test]
+    fn empty_pages_keep_the_cursor() {
+        let (page, next_cursor) = block_range_page(ANCHOR, 4, 10, &[(ANCHOR, true)]);
+        assert!(page.is_empty());
+        assert_eq!(next_cursor, "anchor:4");
+    }
 }
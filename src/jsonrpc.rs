@@ -0,0 +1,506 @@
+use crate::{
+    address,
+    auth::{self, Claims},
+    client::proto::{
+        kaspad_request::Payload as RequestPayload, kaspad_response::Payload as ResponsePayload,
+        GetBalanceByAddressResponseMessage, GetBalancesByAddressesResponseMessage,
+        GetBlockResponseMessage, RpcBalancesByAddressesEntry, RpcTransaction,
+    },
+    error::{ErrorCode, FieldError, RpcError},
+    handlers, rate_limit,
+    request_id::RequestId,
+    transaction::ValidatedTransaction,
+    AppState,
+};
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{Extensions, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+/// Upper bound on calls in one batch
+const MAX_BATCH_SIZE: usize = 100;
+
+// Error codes defined by the JSON-RPC 2.0 specification
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const INTERNAL_ERROR: i64 = -32603;
+
+// Server-defined error codes
+const NODE_ERROR: i64 = -32000;
+const UPSTREAM_UNAVAILABLE: i64 = -32001;
+const UNAUTHORIZED: i64 = -32003;
+const UPSTREAM_TIMEOUT: i64 = -32004;
+const RATE_LIMITED: i64 = -32005;
+
+/// Methods that change node state and always need an admin token
+const ADMIN_METHODS: [&str; 5] = [
+    "addPeer",
+    "ban",
+    "unban",
+    "shutdown",
+    "resolveFinalityConflict",
+];
+
+/// Methods that broadcast to the network, gated like `/rpc/submitTransaction`
+const SUBMITTER_METHODS: [&str; 3] = [
+    "submitBlock",
+    "submitTransaction",
+    "submitTransactionReplacement",
+];
+
+#[derive(Debug, Serialize)]
+struct JsonRpcResponse {
+    jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<JsonRpcError>,
+    id: Value,
+}
+
+impl JsonRpcResponse {
+    /// Errors are tagged with the ID of the HTTP request that carried them
+    fn new(id: Value, result: Result<Value, JsonRpcError>, request_id: &str) -> Self {
+        let (result, error) = match result {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error.with_request_id(request_id))),
+        };
+        Self {
+            jsonrpc: "2.0",
+            result,
+            error,
+            id,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+impl JsonRpcError {
+    /// Errors raised before reaching the node carry an `errorCode` too,
+    /// so every error has the same `data` as the REST error body
+    fn new(code: i64, error_code: ErrorCode, message: impl Into<String>) -> Self {
+        let mut data = Map::new();
+        data.insert("errorCode".into(), serde_json::json!(error_code));
+        Self {
+            code,
+            message: message.into(),
+            data: Some(Value::Object(data)),
+        }
+    }
+
+    fn with_request_id(mut self, request_id: &str) -> Self {
+        let data = self
+            .data
+            .get_or_insert_with(|| Value::Object(Map::new()));
+        if let Value::Object(data) = data {
+            data.insert("requestId".into(), request_id.into());
+        }
+        self
+    }
+}
+
+impl From<RpcError> for JsonRpcError {
+    fn from(error: RpcError) -> Self {
+        let mut data = Map::new();
+        data.insert("errorCode".into(), serde_json::json!(error.code()));
+
+        let (code, message) = match error {
+            RpcError::Connection(msg) => (UPSTREAM_UNAVAILABLE, msg),
+            RpcError::Timeout(msg) | RpcError::Unconfirmed(msg) => (UPSTREAM_TIMEOUT, msg),
+            RpcError::Kaspa(msg) => (NODE_ERROR, msg),
+            RpcError::BadRequest(msg) => (INVALID_PARAMS, msg),
+            RpcError::Validation(fields) => {
+                let message = format!("{} invalid fields", fields.len());
+                data.insert("fields".into(), serde_json::json!(fields));
+                (INVALID_PARAMS, message)
+            }
+            RpcError::Auth(msg) | RpcError::Forbidden(msg) => (UNAUTHORIZED, msg),
+            RpcError::RateLimited(secs) => {
+                data.insert("retryAfter".into(), secs.into());
+                (
+                    RATE_LIMITED,
+                    format!("Rate limit exceeded, retry after {}s", secs),
+                )
+            }
+            other => (INTERNAL_ERROR, other.to_string()),
+        };
+
+        Self {
+            code,
+            message,
+            data: Some(Value::Object(data)),
+        }
+    }
+}
+
+/// Who is calling, captured once per HTTP request
+struct Caller {
+    client: String,
+    claims: Option<Claims>,
+    request_id: String,
+}
+
+/// JSON-RPC 2.0 endpoint covering the node's request/response methods
+///
+/// Method names are the `KaspadRequest` payload names without the
+/// `Request` suffix (`getBlockDagInfo`, `getMempoolEntries`, ...) and
+/// params are the request message as a JSON object with proto field
+/// names. Results are the response message minus its `error` field.
+/// Batches run concurrently; every call is charged to the rate limiter
+/// separately. Methods with a REST counterpart go through its checks and
+/// caches.
+pub async fn handle(
+    State(state): State<AppState>,
+    extensions: Extensions,
+    body: Bytes,
+) -> Response {
+    let caller = Arc::new(Caller {
+        client: rate_limit::client_key(&extensions),
+        claims: extensions.get::<Claims>().cloned(),
+        request_id: extensions
+            .get::<RequestId>()
+            .map(|id| id.0.clone())
+            .unwrap_or_default(),
+    });
+
+    let request: Value = match serde_json::from_slice(&body) {
+        Ok(request) => request,
+        Err(e) => {
+            let error = JsonRpcError::new(
+                PARSE_ERROR,
+                ErrorCode::BadRequest,
+                format!("Parse error: {}", e),
+            );
+            return Json(JsonRpcResponse::new(Value::Null, Err(error), &caller.request_id))
+                .into_response();
+        }
+    };
+
+    let calls = match request {
+        Value::Array(calls) => calls,
+        call => {
+            return match handle_call(&state, &caller, call).await {
+                Some(response) => Json(response).into_response(),
+                None => StatusCode::NO_CONTENT.into_response(),
+            };
+        }
+    };
+
+    if calls.is_empty() || calls.len() > MAX_BATCH_SIZE {
+        let error = JsonRpcError::new(
+            INVALID_REQUEST,
+            ErrorCode::BadRequest,
+            format!("Batch must hold between 1 and {} calls", MAX_BATCH_SIZE),
+        );
+        return Json(JsonRpcResponse::new(Value::Null, Err(error), &caller.request_id))
+            .into_response();
+    }
+
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, call) in calls.into_iter().enumerate() {
+        let state = state.clone();
+        let caller = caller.clone();
+        tasks.spawn(async move { (index, handle_call(&state, &caller, call).await) });
+    }
+
+    let mut responses = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((index, Some(response))) => responses.push((index, response)),
+            Ok((_, None)) => {}
+            Err(e) => tracing::error!("JSON-RPC call panicked: {}", e),
+        }
+    }
+    responses.sort_by_key(|(index, _)| *index);
+
+    // A batch of notifications gets no body at all
+    if responses.is_empty() {
+        return StatusCode::NO_CONTENT.into_response();
+    }
+    let responses: Vec<_> = responses
+        .into_iter()
+        .map(|(_, response)| response)
+        .collect();
+    Json(responses).into_response()
+}
+
+/// Run one call; notifications (no `id`) produce no response
+async fn handle_call(state: &AppState, caller: &Caller, call: Value) -> Option<JsonRpcResponse> {
+    let Value::Object(mut call) = call else {
+        let error = JsonRpcError::new(
+            INVALID_REQUEST,
+            ErrorCode::BadRequest,
+            "Request must be an object",
+        );
+        return Some(JsonRpcResponse::new(Value::Null, Err(error), &caller.request_id));
+    };
+
+    let id = call.remove("id");
+    let version = call.remove("jsonrpc");
+    let (Some(Value::String(method)), Some(Value::String(version))) =
+        (call.remove("method"), version)
+    else {
+        // Malformed calls are answered even without an id
+        let error = JsonRpcError::new(
+            INVALID_REQUEST,
+            ErrorCode::BadRequest,
+            "Expected \"jsonrpc\": \"2.0\" and a string \"method\"",
+        );
+        return Some(JsonRpcResponse::new(
+            id.unwrap_or(Value::Null),
+            Err(error),
+            &caller.request_id,
+        ));
+    };
+    if version != "2.0" {
+        let error = JsonRpcError::new(
+            INVALID_REQUEST,
+            ErrorCode::BadRequest,
+            "Only JSON-RPC 2.0 is supported",
+        );
+        return Some(JsonRpcResponse::new(
+            id.unwrap_or(Value::Null),
+            Err(error),
+            &caller.request_id,
+        ));
+    }
+
+    let result = execute(state, caller, &method, call.remove("params")).await;
+    id.map(|id| JsonRpcResponse::new(id, result, &caller.request_id))
+}
+
+async fn execute(
+    state: &AppState,
+    caller: &Caller,
+    method: &str,
+    params: Option<Value>,
+) -> Result<Value, JsonRpcError> {
+    if method.starts_with("notify") || method.starts_with("stopNotifying") {
+        return Err(JsonRpcError::new(
+            METHOD_NOT_FOUND,
+            ErrorCode::NotFound,
+            "Notifications are served over the /ws endpoint",
+        ));
+    }
+
+    authorize(caller, method, state.auth_enabled)?;
+    // Unknown methods are rejected before charging, as the rate limit
+    // metrics are labelled by method
+    let payload = request_payload(method, params)?;
+    state.rate_limiter.charge(&caller.client, method)?;
+    let payload = validate_submission(payload)?;
+    let response = dispatch(state, payload).await?;
+
+    // The payload serializes as `{"<method>Response": {...}}`
+    let response = serde_json::to_value(&response)
+        .map_err(|e| JsonRpcError::new(INTERNAL_ERROR, ErrorCode::Internal, e.to_string()))?;
+    let mut result = match response {
+        Value::Object(mut response) => response.remove(&format!("{}Response", method)),
+        _ => None,
+    }
+    .ok_or_else(|| {
+        JsonRpcError::new(
+            INTERNAL_ERROR,
+            ErrorCode::InvalidUpstreamResponse,
+            "Unexpected response from node",
+        )
+    })?;
+
+    if let Some(error) = result
+        .as_object_mut()
+        .and_then(|fields| fields.remove("error"))
+    {
+        if !error.is_null() {
+            let message = error["message"]
+                .as_str()
+                .unwrap_or("Node error")
+                .to_string();
+            return Err(RpcError::Kaspa(message).into());
+        }
+    }
+
+    Ok(result)
+}
+
+/// Send a call to the node, through the REST handler helpers where a
+/// method has them
+async fn dispatch(state: &AppState, payload: RequestPayload) -> Result<ResponsePayload, RpcError> {
+    match payload {
+        RequestPayload::GetBlockRequest(request) => {
+            handlers::validate_block_hash(&request.hash)?;
+            let block =
+                handlers::fetch_block(state, &request.hash, request.include_transactions).await?;
+            Ok(ResponsePayload::GetBlockResponse(GetBlockResponseMessage {
+                block: Some(block.as_ref().clone()),
+                error: None,
+            }))
+        }
+        RequestPayload::GetBalanceByAddressRequest(request) => {
+            address::validate_one("params.address", &request.address)?;
+            let balance = handlers::fetch_balance(state, &request.address).await?;
+            Ok(ResponsePayload::GetBalanceByAddressResponse(
+                GetBalanceByAddressResponseMessage {
+                    balance,
+                    error: None,
+                },
+            ))
+        }
+        RequestPayload::GetBalancesByAddressesRequest(request) => {
+            handlers::validate_addresses(
+                "params.addresses",
+                &request.addresses,
+                handlers::MAX_BALANCE_ADDRESSES,
+            )?;
+            let balances = handlers::fetch_balances(state, &request.addresses).await?;
+            let entries = request
+                .addresses
+                .into_iter()
+                .zip(balances)
+                .map(|(address, balance)| RpcBalancesByAddressesEntry {
+                    address,
+                    balance,
+                    error: None,
+                })
+                .collect();
+            Ok(ResponsePayload::GetBalancesByAddressesResponse(
+                GetBalancesByAddressesResponseMessage {
+                    entries,
+                    error: None,
+                },
+            ))
+        }
+        payload => {
+            match &payload {
+                RequestPayload::GetUtxosByAddressesRequest(request) => {
+                    handlers::validate_addresses(
+                        "params.addresses",
+                        &request.addresses,
+                        handlers::MAX_UTXO_ADDRESSES,
+                    )?;
+                }
+                RequestPayload::GetMempoolEntriesByAddressesRequest(request) => {
+                    handlers::validate_addresses(
+                        "params.addresses",
+                        &request.addresses,
+                        handlers::MAX_MEMPOOL_ADDRESSES,
+                    )?;
+                }
+                _ => {}
+            }
+            state
+                .upstreams
+                .call(|client| Box::pin(client.call(payload.clone())))
+                .await
+        }
+    }
+}
+
+/// Check the caller's role for methods that need one
+fn authorize(caller: &Caller, method: &str, auth_enabled: bool) -> Result<(), JsonRpcError> {
+    let required = if ADMIN_METHODS.contains(&method) {
+        auth::ADMIN_ROLE
+    } else if SUBMITTER_METHODS.contains(&method) {
+        auth::SUBMITTER_ROLE
+    } else {
+        return Ok(());
+    };
+
+    match &caller.claims {
+        Some(claims) if claims.role == required || claims.role == auth::ADMIN_ROLE => Ok(()),
+        Some(_) => Err(JsonRpcError::new(
+            UNAUTHORIZED,
+            ErrorCode::Forbidden,
+            format!("Requires role '{}'", required),
+        )),
+        // With authentication disabled only node administration stays locked
+        None if auth_enabled || required == auth::ADMIN_ROLE => Err(JsonRpcError::new(
+            UNAUTHORIZED,
+            ErrorCode::Unauthorized,
+            format!("Requires a token with role '{}'", required),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Run submitted transactions through the checks of `/rpc/submitTransaction`
+///
+/// The node receives the normalized transaction, as it would over REST.
+fn validate_submission(payload: RequestPayload) -> Result<RequestPayload, RpcError> {
+    fn validate(transaction: Option<RpcTransaction>) -> Result<Option<RpcTransaction>, RpcError> {
+        let transaction = transaction.ok_or_else(|| {
+            RpcError::Validation(vec![FieldError {
+                field: "params.transaction".into(),
+                message: "A transaction is required".into(),
+            }])
+        })?;
+        let validated = ValidatedTransaction::validate(&transaction, "params.transaction")?;
+        Ok(Some(validated.to_proto()))
+    }
+
+    Ok(match payload {
+        RequestPayload::SubmitTransactionRequest(mut request) => {
+            request.transaction = validate(request.transaction)?;
+            RequestPayload::SubmitTransactionRequest(request)
+        }
+        RequestPayload::SubmitTransactionReplacementRequest(mut request) => {
+            request.transaction = validate(request.transaction)?;
+            RequestPayload::SubmitTransactionReplacementRequest(request)
+        }
+        payload => payload,
+    })
+}
+
+/// Build the proto request payload for `method` from its params
+fn request_payload(method: &str, params: Option<Value>) -> Result<RequestPayload, JsonRpcError> {
+    let key = format!("{}Request", method);
+
+    // Every request message defaults its fields, so an empty object
+    // only fails to parse when the method does not exist
+    let mut probe = Map::new();
+    probe.insert(key.clone(), Value::Object(Map::new()));
+    if serde_json::from_value::<RequestPayload>(Value::Object(probe)).is_err() {
+        return Err(JsonRpcError::new(
+            METHOD_NOT_FOUND,
+            ErrorCode::NotFound,
+            format!("Method not found: {}", method),
+        ));
+    }
+
+    let params = match params {
+        None | Some(Value::Null) => Value::Object(Map::new()),
+        Some(params @ Value::Object(_)) => params,
+        Some(_) => {
+            return Err(JsonRpcError::new(
+                INVALID_PARAMS,
+                ErrorCode::BadRequest,
+                "Params must be an object of request fields",
+            ))
+        }
+    };
+
+    let mut payload = Map::new();
+    payload.insert(key, params);
+    serde_json::from_value(Value::Object(payload))
+        .map_err(|e| {
+            JsonRpcError::new(
+                INVALID_PARAMS,
+                ErrorCode::BadRequest,
+                format!("Invalid params: {}", e),
+            )
+        })
+}
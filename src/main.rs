@@ -1,13 +1,26 @@
+mod address;
+mod api_keys;
 mod auth;
+mod balances;
+mod block_cache;
+mod circuit_breaker;
 mod client;
 mod error;
 mod handlers;
+mod jsonrpc;
 mod metrics;
 mod models;
+mod rate_limit;
+mod request_id;
+mod sse;
+mod subscriptions;
+mod transaction;
+mod upstream;
 mod websocket;
 
 use axum::{
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
 use std::net::SocketAddr;
@@ -31,64 +44,315 @@ async fn main() -> anyhow::Result<()> {
     // Load configuration
     let config = load_config()?;
     
-    // Initialize Kaspa gRPC client
-    let kaspa_client = client::KaspaClient::new(&config.kaspa_rpc_url).await?;
-    tracing::info!("✓ Connected to Kaspa node at {}", config.kaspa_rpc_url);
+    // Initialize upstream Kaspa node pool
+    let upstreams = upstream::UpstreamPool::new(&config.kaspa_rpc_urls, &config.kaspa_network)?;
+    upstreams.spawn_health_checks(std::time::Duration::from_secs(
+        config.health_check_interval_secs,
+    ));
+    let upstreams = std::sync::Arc::new(upstreams);
+    let balances = balances::BalanceCache::new(
+        &upstreams,
+        std::time::Duration::from_millis(config.balance_cache_ttl_ms),
+    );
+    let subscriptions =
+        subscriptions::SubscriptionManager::new(upstreams.clone(), balances.clone());
+    let blocks = block_cache::BlockCache::new(
+        config.block_cache_bytes,
+        std::time::Duration::from_millis(config.block_cache_ttl_ms),
+    );
 
-    // Build router
-    let app = Router::new()
-        // Health check
-        .route("/health", get(handlers::health_check))
-        .route("/metrics", get(handlers::metrics))
-        
+    // API keys traded for tokens at /auth/token
+    let api_keys = api_keys::ApiKeyStore::load(
+        &config.api_keys_path,
+        config.admin_api_key.as_deref(),
+    )?;
+
+    let auth_enabled = config.auth_enabled;
+    let state = AppState {
+        upstreams,
+        subscriptions,
+        balances,
+        blocks,
+        api_keys: std::sync::Arc::new(api_keys),
+        rate_limiter: rate_limit::RateLimiter::new(
+            config.rate_limit_rps,
+            config.rate_limit_burst,
+            config.rate_limit_costs.clone(),
+        )?,
+        jwt_secret: config.jwt_secret.clone(),
+        auth_enabled,
+    };
+
+    // Routes that require a token when authentication is enabled
+    let protected = Router::new()
         // Core RPC endpoints
         .route("/rpc/getBlock", post(handlers::get_block))
-        .route("/rpc/submitTransaction", post(handlers::submit_transaction))
+        .route("/rpc/getBlocks", post(handlers::get_blocks))
+        .route("/rpc/getBlockRange", post(handlers::get_block_range))
+        .route("/rpc/getHeaders", post(handlers::get_headers))
+        .route(
+            "/rpc/getVirtualChainFromBlock",
+            post(handlers::get_virtual_chain_from_block),
+        )
+        .route(
+            "/rpc/submitTransaction",
+            post(handlers::submit_transaction).route_layer(middleware::from_fn(
+                move |request, next| {
+                    auth::require_role(auth::SUBMITTER_ROLE, auth_enabled, request, next)
+                },
+            )),
+        )
+        .route(
+            "/rpc/submitTransactionReplacement",
+            post(handlers::submit_transaction_replacement).route_layer(middleware::from_fn(
+                move |request, next| {
+                    auth::require_role(auth::SUBMITTER_ROLE, auth_enabled, request, next)
+                },
+            )),
+        )
         .route("/rpc/getDAGTips", post(handlers::get_dag_tips))
+        .route("/rpc/getUtxosByAddresses", post(handlers::get_utxos_by_addresses))
+        .route("/rpc/getBalance", post(handlers::get_balance))
+        .route("/rpc/getBalances", post(handlers::get_balances))
+        .route("/rpc/getMempoolEntry", post(handlers::get_mempool_entry))
+        .route("/rpc/getMempoolEntries", post(handlers::get_mempool_entries))
+        .route(
+            "/rpc/getMempoolEntriesByAddresses",
+            post(handlers::get_mempool_entries_by_addresses),
+        )
+        .route("/rpc/getFeeEstimate", post(handlers::get_fee_estimate))
+        .route("/rpc/getInfo", post(handlers::get_info))
+        .route("/rpc/getServerInfo", post(handlers::get_server_info))
+        .route("/rpc/getSyncStatus", post(handlers::get_sync_status))
+        .route("/rpc/getCurrentNetwork", post(handlers::get_current_network))
+        .route("/rpc/getCoinSupply", post(handlers::get_coin_supply))
+        .route("/rpc/status", get(handlers::status))
         
         // WebSocket for subscriptions
+        .route("/ws", get(websocket::subscribe))
         .route("/ws/subscribeUTXO", get(websocket::subscribe_utxo))
+
+        // Server-Sent Events for browser clients
+        .route("/sse/sinkBlueScore", get(sse::sink_blue_score))
+        .route("/sse/virtualDaaScore", get(sse::virtual_daa_score))
+
+        // Runs after authentication so clients are keyed by token subject
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            rate_limit::rate_limit,
+        ))
+
+        // JSON-RPC 2.0; charges each call of a batch itself
+        .route("/jsonrpc", post(jsonrpc::handle));
+
+    let protected = if config.auth_enabled {
+        protected.route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ))
+    } else {
+        tracing::warn!("⚠️ Authentication disabled by AUTH_ENABLED=false, anyone can call /rpc, /ws and /sse");
+        protected
+    };
+
+    // Key management always requires an admin token
+    let admin = Router::new()
+        .route(
+            "/admin/apiKeys",
+            get(handlers::list_api_keys).post(handlers::create_api_key),
+        )
+        .route("/admin/apiKeys/:id", delete(handlers::revoke_api_key))
+        .route_layer(middleware::from_fn(move |request, next| {
+            auth::require_role(auth::ADMIN_ROLE, auth_enabled, request, next)
+        }))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::require_auth,
+        ));
+
+    // Build router
+    let app = Router::new()
+        // Health check
+        .route("/health", get(handlers::health_check))
+        .route("/metrics", get(handlers::metrics))
+        .route(
+            "/auth/token",
+            post(handlers::issue_token).route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                rate_limit::rate_limit,
+            )),
+        )
+        .merge(protected)
+        .merge(admin)
         
         // Middleware
+        .layer(middleware::from_fn(request_id::assign))
         .layer(CorsLayer::permissive())
         .layer(TraceLayer::new_for_http())
-        .with_state(AppState {
-            kaspa_client: std::sync::Arc::new(kaspa_client),
-            jwt_secret: config.jwt_secret.clone(),
-        });
+        .with_state(state);
 
     // Start server
     let addr: SocketAddr = config.bind_address.parse()?;
     tracing::info!("🌐 Listening on {}", addr);
     
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+    // Peer addresses key the rate limiter for anonymous clients
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
 
 #[derive(Clone)]
 struct AppState {
-    kaspa_client: std::sync::Arc<client::KaspaClient>,
+    upstreams: std::sync::Arc<upstream::UpstreamPool>,
+    subscriptions: std::sync::Arc<subscriptions::SubscriptionManager>,
+    balances: std::sync::Arc<balances::BalanceCache>,
+    blocks: std::sync::Arc<block_cache::BlockCache>,
+    api_keys: std::sync::Arc<api_keys::ApiKeyStore>,
+    rate_limiter: std::sync::Arc<rate_limit::RateLimiter>,
     jwt_secret: String,
+    auth_enabled: bool,
 }
 
 #[derive(Debug, serde::Deserialize)]
 struct Config {
-    kaspa_rpc_url: String,
+    kaspa_rpc_urls: Vec<String>,
+    kaspa_network: String,
     bind_address: String,
     jwt_secret: String,
+    auth_enabled: bool,
+    api_keys_path: String,
+    admin_api_key: Option<String>,
+    rate_limit_rps: f64,
+    rate_limit_burst: f64,
+    rate_limit_costs: std::collections::HashMap<String, f64>,
+    balance_cache_ttl_ms: u64,
+    block_cache_bytes: usize,
+    block_cache_ttl_ms: u64,
+    health_check_interval_secs: u64,
+}
+
+/// Placeholder secrets from the docs and earlier defaults
+const PLACEHOLDER_JWT_SECRETS: [&str; 2] = [
+    "CHANGE_ME_IN_PRODUCTION",
+    "your-super-secret-key-here-min-32-chars",
+];
+
+/// Shortest JWT secret accepted, in bytes
+const MIN_JWT_SECRET_LEN: usize = 32;
+
+/// Read `JWT_SECRET`, refusing to start without a usable one
+///
+/// Admin tokens mint API keys, so a guessable secret hands out admin
+/// access to anyone who can sign one.
+fn load_jwt_secret() -> anyhow::Result<String> {
+    let secret = std::env::var("JWT_SECRET").unwrap_or_default();
+    if secret.trim().is_empty() {
+        anyhow::bail!("JWT_SECRET is not set, generate one with `openssl rand -hex 32`");
+    }
+    if PLACEHOLDER_JWT_SECRETS.contains(&secret.as_str()) {
+        anyhow::bail!(
+            "JWT_SECRET is still a placeholder, generate one with `openssl rand -hex 32`"
+        );
+    }
+    if secret.len() < MIN_JWT_SECRET_LEN {
+        anyhow::bail!(
+            "JWT_SECRET must be at least {} bytes, got {}",
+            MIN_JWT_SECRET_LEN,
+            secret.len()
+        );
+    }
+    Ok(secret)
+}
+
+/// Read `KASPA_NETWORK`, the network ID every node must report
+///
+/// Addresses are checked against its prefix, and nodes on any other
+/// network, another testnet included, are kept out of rotation.
+fn load_network() -> anyhow::Result<String> {
+    let network = std::env::var("KASPA_NETWORK")
+        .unwrap_or_else(|_| "mainnet".to_string())
+        .trim()
+        .to_ascii_lowercase();
+    address::set_network(&network).map_err(|e| anyhow::anyhow!("KASPA_NETWORK: {}", e))?;
+    Ok(network)
+}
+
+/// Longest balance cache TTL accepted, in milliseconds
+const MAX_BALANCE_CACHE_TTL_MS: u64 = 5_000;
+
+/// Read `BALANCE_CACHE_TTL_MS`, refusing TTLs that would serve stale balances
+///
+/// Only addresses watched over WebSocket are evicted on UTXO changes;
+/// polled addresses rely on the TTL alone.
+fn load_balance_cache_ttl_ms() -> anyhow::Result<u64> {
+    let ttl_ms = std::env::var("BALANCE_CACHE_TTL_MS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1000);
+    if ttl_ms > MAX_BALANCE_CACHE_TTL_MS {
+        anyhow::bail!(
+            "BALANCE_CACHE_TTL_MS must be at most {}, got {}",
+            MAX_BALANCE_CACHE_TTL_MS,
+            ttl_ms
+        );
+    }
+    Ok(ttl_ms)
 }
 
 fn load_config() -> anyhow::Result<Config> {
     dotenv::dotenv().ok();
     
     Ok(Config {
-        kaspa_rpc_url: std::env::var("KASPA_RPC_URL")
-            .unwrap_or_else(|_| "http://localhost:16110".to_string()),
+        // KASPA_RPC_URLS takes a comma-separated list, in order of preference
+        kaspa_rpc_urls: std::env::var("KASPA_RPC_URLS")
+            .or_else(|_| std::env::var("KASPA_RPC_URL"))
+            .unwrap_or_else(|_| "http://localhost:16110".to_string())
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect(),
+        kaspa_network: load_network()?,
         bind_address: std::env::var("BIND_ADDRESS")
             .unwrap_or_else(|_| "0.0.0.0:8080".to_string()),
-        jwt_secret: std::env::var("JWT_SECRET")
-            .unwrap_or_else(|_| "CHANGE_ME_IN_PRODUCTION".to_string()),
+        jwt_secret: load_jwt_secret()?,
+        // Only an explicit AUTH_ENABLED=false opens the API to anonymous callers
+        auth_enabled: std::env::var("AUTH_ENABLED")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(true),
+        api_keys_path: std::env::var("API_KEYS_PATH")
+            .unwrap_or_else(|_| "api_keys.json".to_string()),
+        admin_api_key: std::env::var("ADMIN_API_KEY").ok().filter(|key| !key.is_empty()),
+        rate_limit_rps: std::env::var("RATE_LIMIT_RPS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(50.0),
+        rate_limit_burst: std::env::var("RATE_LIMIT_BURST")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(100.0),
+        // Method costs as `method=cost` pairs, e.g. `submitTransaction=5,getBlock=1`
+        rate_limit_costs: rate_limit::parse_costs(
+            &std::env::var("RATE_LIMIT_COSTS")
+                .unwrap_or_else(|_| rate_limit::DEFAULT_COSTS.to_string()),
+        )?,
+        balance_cache_ttl_ms: load_balance_cache_ttl_ms()?,
+        block_cache_bytes: std::env::var("BLOCK_CACHE_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(64 * 1024 * 1024),
+        block_cache_ttl_ms: std::env::var("BLOCK_CACHE_TTL_MS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10_000),
+        health_check_interval_secs: std::env::var("HEALTH_CHECK_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10),
     })
 }
@@ -1,5 +1,9 @@
+use crate::{circuit_breaker::CircuitState, upstream::UpstreamHealth};
 use lazy_static::lazy_static;
-use prometheus::{Encoder, Histogram, HistogramOpts, Registry, TextEncoder};
+use prometheus::{
+    CounterVec, Encoder, GaugeVec, Histogram, HistogramOpts, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry,
+    TextEncoder,
+};
 use std::collections::HashMap;
 
 lazy_static! {
@@ -8,7 +12,30 @@ lazy_static! {
         let mut map = HashMap::new();
         
         // Create histogram for each endpoint
-        let endpoints = vec!["get_block", "submit_transaction", "get_dag_tips", "subscribe_utxo"];
+        let endpoints = vec![
+            "get_block",
+            "get_blocks",
+            "get_block_range",
+            "get_headers",
+            "get_virtual_chain_from_block",
+            "submit_transaction",
+            "submit_transaction_replacement",
+            "get_dag_tips",
+            "subscribe_utxo",
+            "get_utxos_by_addresses",
+            "get_balance",
+            "get_balances",
+            "get_mempool_entry",
+            "get_mempool_entries",
+            "get_mempool_entries_by_addresses",
+            "get_fee_estimate",
+            "get_info",
+            "get_server_info",
+            "get_sync_status",
+            "get_current_network",
+            "get_coin_supply",
+            "status",
+        ];
         
         for endpoint in endpoints {
             let opts = HistogramOpts::new(
@@ -24,6 +51,137 @@ lazy_static! {
         
         map
     };
+    static ref UPSTREAM_HEALTHY: IntGaugeVec = {
+        let gauge = IntGaugeVec::new(
+            Opts::new("kaspa_upstream_healthy", "Whether the upstream node answers health probes"),
+            &["upstream"],
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(gauge.clone())).unwrap();
+        gauge
+    };
+    static ref UPSTREAM_SYNCED: IntGaugeVec = {
+        let gauge = IntGaugeVec::new(
+            Opts::new("kaspa_upstream_synced", "Whether the upstream node reports itself synced"),
+            &["upstream"],
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(gauge.clone())).unwrap();
+        gauge
+    };
+    static ref UPSTREAM_PROBE_LATENCY: GaugeVec = {
+        let gauge = GaugeVec::new(
+            Opts::new("kaspa_upstream_probe_latency_ms", "Latency of the last successful health probe in milliseconds"),
+            &["upstream"],
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(gauge.clone())).unwrap();
+        gauge
+    };
+    static ref UPSTREAM_CIRCUIT_STATE: IntGaugeVec = {
+        let gauge = IntGaugeVec::new(
+            Opts::new("kaspa_upstream_circuit_state", "Circuit breaker state per upstream: 0 closed, 1 half-open, 2 open"),
+            &["upstream"],
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(gauge.clone())).unwrap();
+        gauge
+    };
+    static ref UPSTREAM_RETRIES: IntCounterVec = {
+        let counter = IntCounterVec::new(
+            Opts::new("kaspa_upstream_retries_total", "Calls retried against an upstream node"),
+            &["upstream"],
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(counter.clone())).unwrap();
+        counter
+    };
+    static ref UPSTREAM_TIMEOUTS: IntCounterVec = {
+        let counter = IntCounterVec::new(
+            Opts::new("kaspa_upstream_timeouts_total", "Calls an upstream node did not answer in time"),
+            &["upstream"],
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(counter.clone())).unwrap();
+        counter
+    };
+    static ref UTXO_SUBSCRIBERS: IntGauge = {
+        let gauge = IntGauge::new("kaspa_utxo_subscribers", "Active UTXO change subscribers").unwrap();
+        REGISTRY.register(Box::new(gauge.clone())).unwrap();
+        gauge
+    };
+    static ref UTXO_WATCHED_ADDRESSES: IntGauge = {
+        let gauge = IntGauge::new(
+            "kaspa_utxo_watched_addresses",
+            "Distinct addresses registered for UTXO notifications upstream",
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(gauge.clone())).unwrap();
+        gauge
+    };
+    static ref SUBSCRIBER_EVENTS_DROPPED: IntCounterVec = {
+        let counter = IntCounterVec::new(
+            Opts::new("kaspa_subscriber_events_dropped_total", "Events dropped for subscribers that fell behind"),
+            &["topic"],
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(counter.clone())).unwrap();
+        counter
+    };
+    static ref UPSTREAM_FAILOVERS: IntCounterVec = {
+        let counter = IntCounterVec::new(
+            Opts::new("kaspa_upstream_failovers_total", "Calls that failed over away from an upstream node"),
+            &["upstream"],
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(counter.clone())).unwrap();
+        counter
+    };
+    static ref RATE_LIMITED: IntCounterVec = {
+        let counter = IntCounterVec::new(
+            Opts::new("kaspa_rpc_rate_limited_total", "Requests rejected by the rate limiter"),
+            &["method"],
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(counter.clone())).unwrap();
+        counter
+    };
+    static ref RATE_LIMIT_COST: CounterVec = {
+        let counter = CounterVec::new(
+            Opts::new("kaspa_rpc_rate_limit_cost_total", "Rate limit cost charged to admitted requests"),
+            &["method"],
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(counter.clone())).unwrap();
+        counter
+    };
+    static ref RATE_LIMIT_CLIENTS: IntGauge = {
+        let gauge = IntGauge::new(
+            "kaspa_rpc_rate_limit_clients",
+            "Clients with a partially drained rate limit bucket",
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(gauge.clone())).unwrap();
+        gauge
+    };
+    static ref CACHE_REQUESTS: IntCounterVec = {
+        let counter = IntCounterVec::new(
+            Opts::new("kaspa_cache_requests_total", "Cache lookups by cache and result"),
+            &["cache", "result"],
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(counter.clone())).unwrap();
+        counter
+    };
+    static ref CACHE_SIZE: IntGaugeVec = {
+        let gauge = IntGaugeVec::new(
+            Opts::new("kaspa_cache_size_bytes", "Encoded size of the entries held per cache"),
+            &["cache"],
+        )
+        .unwrap();
+        REGISTRY.register(Box::new(gauge.clone())).unwrap();
+        gauge
+    };
 }
 
 /// Record latency for an endpoint
@@ -42,6 +200,83 @@ pub fn record_latency(endpoint: &str, latency_ms: f64) {
     }
 }
 
+/// Record the latest health state of an upstream node
+pub fn record_upstream_health(upstream: &str, health: &UpstreamHealth) {
+    UPSTREAM_HEALTHY
+        .with_label_values(&[upstream])
+        .set(health.healthy as i64);
+    UPSTREAM_SYNCED
+        .with_label_values(&[upstream])
+        .set(health.synced as i64);
+    if let Some(latency_ms) = health.latency_ms {
+        UPSTREAM_PROBE_LATENCY
+            .with_label_values(&[upstream])
+            .set(latency_ms);
+    }
+}
+
+/// Count a call that failed over away from an upstream node
+pub fn record_upstream_failover(upstream: &str) {
+    UPSTREAM_FAILOVERS.with_label_values(&[upstream]).inc();
+}
+
+/// Record a circuit breaker state change
+pub fn record_circuit_state(upstream: &str, state: CircuitState) {
+    let value = match state {
+        CircuitState::Closed => 0,
+        CircuitState::HalfOpen => 1,
+        CircuitState::Open => 2,
+    };
+    UPSTREAM_CIRCUIT_STATE.with_label_values(&[upstream]).set(value);
+}
+
+/// Count a call retried against an upstream node
+pub fn record_upstream_retry(upstream: &str) {
+    UPSTREAM_RETRIES.with_label_values(&[upstream]).inc();
+}
+
+/// Count a call an upstream node did not answer in time
+pub fn record_upstream_timeout(upstream: &str) {
+    UPSTREAM_TIMEOUTS.with_label_values(&[upstream]).inc();
+}
+
+/// Record the size of the shared UTXO subscription
+pub fn record_utxo_subscriptions(subscribers: usize, addresses: usize) {
+    UTXO_SUBSCRIBERS.set(subscribers as i64);
+    UTXO_WATCHED_ADDRESSES.set(addresses as i64);
+}
+
+/// Count an event dropped for a subscriber that fell behind
+pub fn record_subscriber_event_dropped(topic: &str) {
+    SUBSCRIBER_EVENTS_DROPPED.with_label_values(&[topic]).inc();
+}
+
+/// Record a request rejected by the rate limiter
+pub fn record_rate_limited(method: &str) {
+    RATE_LIMITED.with_label_values(&[method]).inc();
+}
+
+/// Record the cost charged to an admitted request
+pub fn record_rate_limit_cost(method: &str, cost: f64) {
+    RATE_LIMIT_COST.with_label_values(&[method]).inc_by(cost);
+}
+
+/// Record how many clients currently hold rate limit state
+pub fn record_rate_limit_clients(clients: usize) {
+    RATE_LIMIT_CLIENTS.set(clients as i64);
+}
+
+/// Record a cache lookup as a hit or miss
+pub fn record_cache_lookup(cache: &str, hit: bool) {
+    let result = if hit { "hit" } else { "miss" };
+    CACHE_REQUESTS.with_label_values(&[cache, result]).inc();
+}
+
+/// Record how many bytes a cache holds
+pub fn record_cache_size(cache: &str, bytes: usize) {
+    CACHE_SIZE.with_label_values(&[cache]).set(bytes as i64);
+}
+
 /// Export metrics in Prometheus format
 pub fn export_metrics() -> String {
     let encoder = TextEncoder::new();
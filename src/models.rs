@@ -1,3 +1,18 @@
+use crate::{
+    address::{self, Address, ScriptType},
+    api_keys::ApiKeyRecord,
+    client::proto::{
+        GetCoinSupplyResponseMessage, GetCurrentNetworkResponseMessage, GetInfoResponseMessage,
+        GetServerInfoResponseMessage, GetSyncStatusResponseMessage,
+        GetVirtualChainFromBlockResponseMessage,
+        RpcAcceptedTransactionIds, RpcBlock, RpcBlockHeader, RpcBlockVerboseData, RpcFeeEstimate,
+        RpcFeeEstimateVerboseExperimentalData, RpcFeerateBucket, RpcMempoolEntry, RpcTransaction,
+        RpcTransactionInput, RpcTransactionOutput, RpcUtxoEntry,
+        VirtualChainChangedNotificationMessage,
+    },
+    error::{ErrorBody, RpcError},
+    upstream::UpstreamHealth,
+};
 use serde::{Deserialize, Serialize};
 
 /// Request to get a block by hash
@@ -13,6 +28,55 @@ fn default_true() -> bool {
     true
 }
 
+/// Request for the selected chain changes since a block
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetVirtualChainFromBlockRequest {
+    #[serde(default)]
+    pub start_hash: String,
+    #[serde(default)]
+    pub include_accepted_transaction_ids: bool,
+    /// Leave out chain blocks with fewer confirmations
+    pub min_confirmation_count: Option<u64>,
+    /// `nextCursor` from the previous call, replaces `startHash`
+    pub cursor: Option<String>,
+}
+
+/// Request for many blocks by hash
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetBlocksRequest {
+    pub hashes: Vec<String>,
+    #[serde(default = "default_true")]
+    pub include_transactions: bool,
+}
+
+/// Request for a page of the DAG above a low hash
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetBlockRangeRequest {
+    /// Block to start after; empty starts at the pruning point
+    #[serde(default)]
+    pub low_hash: String,
+    #[serde(default)]
+    pub include_blocks: bool,
+    #[serde(default)]
+    pub include_transactions: bool,
+    pub limit: Option<usize>,
+    /// `nextCursor` from the previous page, replaces `lowHash`
+    pub cursor: Option<String>,
+}
+
+/// Request for header hashes from a start hash
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetHeadersRequest {
+    pub start_hash: String,
+    pub limit: Option<u64>,
+    #[serde(default = "default_true")]
+    pub is_ascending: bool,
+}
+
 /// Request to submit a transaction
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -20,6 +84,15 @@ pub struct SubmitTransactionRequest {
     pub transaction: TransactionInput,
     #[serde(default)]
     pub allow_orphan: bool,
+    /// Validate and return the ID, hash and mass without broadcasting
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Request to replace a mempool transaction (RBF)
+#[derive(Debug, Deserialize)]
+pub struct SubmitTransactionReplacementRequest {
+    pub transaction: TransactionInput,
 }
 
 /// Simplified transaction input format
@@ -33,6 +106,8 @@ pub struct TransactionInput {
     pub subnetwork_id: Option<String>,
     pub gas: Option<u64>,
     pub payload: Option<String>,
+    /// Committed storage mass; left at 0 the node computes it
+    pub mass: Option<u64>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -65,16 +140,148 @@ pub struct ScriptPublicKey {
     pub version: u16,
 }
 
-/// Request for DAG tips
+/// Request for one mempool transaction
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetMempoolEntryRequest {
+    pub transaction_id: String,
+    /// Also look in the orphan pool
+    #[serde(default)]
+    pub include_orphan_pool: bool,
+    /// Skip the transaction pool, i.e. only look at orphans
+    #[serde(default)]
+    pub filter_transaction_pool: bool,
+}
+
+/// Request for a page of the mempool
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetMempoolEntriesRequest {
+    #[serde(default)]
+    pub include_orphan_pool: bool,
+    #[serde(default)]
+    pub filter_transaction_pool: bool,
+    #[serde(default)]
+    pub sort: MempoolSort,
+    pub limit: Option<usize>,
+    /// `nextCursor` from the previous page
+    pub cursor: Option<String>,
+}
+
+/// Request for the mempool transactions touching a set of addresses
 #[derive(Debug, Deserialize)]
-pub struct GetDAGTipsRequest {}
+#[serde(rename_all = "camelCase")]
+pub struct GetMempoolEntriesByAddressesRequest {
+    pub addresses: Vec<String>,
+    #[serde(default)]
+    pub include_orphan_pool: bool,
+    #[serde(default)]
+    pub filter_transaction_pool: bool,
+    #[serde(default)]
+    pub sort: MempoolSort,
+}
 
-/// Request to subscribe to UTXO changes
+/// Order of returned mempool entries
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MempoolSort {
+    /// Highest fee per gram of mass first
+    #[default]
+    FeeRate,
+    /// Lowest fee per gram of mass first
+    FeeRateAsc,
+    /// By transaction ID
+    TransactionId,
+}
+
+/// Request for fee rate estimates
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetFeeEstimateRequest {
+    /// Transaction mass in grams; adds the total fee to every tier
+    pub mass: Option<u64>,
+    /// Include the node's experimental mempool data
+    #[serde(default)]
+    pub verbose: bool,
+}
+
+/// Request for the balance of one address
 #[derive(Debug, Deserialize)]
-pub struct SubscribeUTXORequest {
+pub struct GetBalanceRequest {
+    pub address: String,
+}
+
+/// Request for the balances of several addresses
+#[derive(Debug, Deserialize)]
+pub struct GetBalancesRequest {
     pub addresses: Vec<String>,
 }
 
+/// Request for the UTXOs of a set of addresses
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GetUtxosByAddressesRequest {
+    pub addresses: Vec<String>,
+    /// Skip UTXOs worth less than this many sompi
+    #[serde(default)]
+    pub min_amount: u64,
+    /// Skip coinbase UTXOs that cannot be spent yet
+    #[serde(default)]
+    pub mature_only: bool,
+    /// DAA score distance after which coinbase outputs become spendable
+    pub coinbase_maturity: Option<u64>,
+    pub limit: Option<usize>,
+    /// `nextCursor` from the previous page
+    pub cursor: Option<String>,
+}
+
+/// Command sent by a client over the multiplexed WebSocket
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case", rename_all_fields = "camelCase")]
+pub enum WsCommand {
+    Subscribe {
+        id: Option<String>,
+        topic: WsTopic,
+        #[serde(default)]
+        addresses: Vec<String>,
+        #[serde(default)]
+        include_accepted_transaction_ids: bool,
+    },
+    Unsubscribe {
+        id: String,
+    },
+    AddAddresses {
+        id: String,
+        addresses: Vec<String>,
+    },
+    RemoveAddresses {
+        id: String,
+        addresses: Vec<String>,
+    },
+}
+
+impl WsCommand {
+    pub fn op(&self) -> &'static str {
+        match self {
+            WsCommand::Subscribe { .. } => "subscribe",
+            WsCommand::Unsubscribe { .. } => "unsubscribe",
+            WsCommand::AddAddresses { .. } => "add_addresses",
+            WsCommand::RemoveAddresses { .. } => "remove_addresses",
+        }
+    }
+}
+
+/// Notification feeds available over the multiplexed WebSocket
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WsTopic {
+    UtxosChanged,
+    BlockAdded,
+    VirtualChainChanged,
+    SinkBlueScoreChanged,
+    VirtualDaaScoreChanged,
+}
+
 /// Generic RPC response wrapper
 #[derive(Debug, Serialize)]
 pub struct RpcResponse<T> {
@@ -93,15 +300,6 @@ impl<T> RpcResponse<T> {
             latency_ms,
         }
     }
-
-    pub fn error(error: String, latency_ms: f64) -> Self {
-        Self {
-            success: false,
-            data: None,
-            error: Some(error),
-            latency_ms,
-        }
-    }
 }
 
 /// Block response
@@ -118,26 +316,51 @@ pub struct BlockResponse {
 #[serde(rename_all = "camelCase")]
 pub struct BlockHeader {
     pub version: u32,
+    /// Parents per block level, starting with the direct parents at level 0
+    pub parents: Vec<BlockLevelParents>,
     pub hash_merkle_root: String,
     pub accepted_id_merkle_root: String,
     pub utxo_commitment: String,
     pub timestamp: i64,
     pub bits: u32,
     pub nonce: u64,
+    /// Blocks in the past, red ones included, minus those merged too late
+    /// for difficulty adjustment; lock times and coinbase maturity use it
     pub daa_score: u64,
     pub blue_work: String,
     pub blue_score: u64,
     pub pruning_point: String,
 }
 
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockLevelParents {
+    pub parent_hashes: Vec<String>,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Transaction {
     pub transaction_id: String,
     pub hash: String,
+    pub version: u32,
     pub mass: u64,
     pub inputs: Vec<TransactionInputVerbose>,
     pub outputs: Vec<TransactionOutput>,
+    pub lock_time: u64,
+    pub subnetwork_id: String,
+    pub gas: u64,
+    pub payload: String,
+    pub verbose_data: Option<TransactionVerboseData>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionVerboseData {
+    pub compute_mass: u64,
+    /// Block the transaction was fetched with, empty outside of blocks
+    pub block_hash: String,
+    pub block_time: u64,
 }
 
 #[derive(Debug, Serialize)]
@@ -146,6 +369,26 @@ pub struct TransactionInputVerbose {
     pub previous_outpoint: OutpointVerbose,
     pub signature_script: String,
     pub sequence: u64,
+    pub sig_op_count: u32,
+    pub verbose_data: Option<TransactionInputVerboseData>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionInputVerboseData {
+    /// Spent UTXO; absent for coinbase inputs or when the node cannot find it
+    pub utxo_entry: Option<UtxoEntry>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UtxoEntry {
+    pub amount: u64,
+    pub script_public_key: String,
+    pub script_version: u32,
+    pub block_daa_score: u64,
+    pub is_coinbase: bool,
+    pub verbose_data: Option<ScriptVerboseData>,
 }
 
 #[derive(Debug, Serialize)]
@@ -160,6 +403,21 @@ pub struct OutpointVerbose {
 pub struct TransactionOutput {
     pub amount: u64,
     pub script_public_key: String,
+    pub script_version: u32,
+    /// `pubkey`, `pubkeyecdsa`, `scripthash` or `nonstandard`
+    pub script_type: &'static str,
+    /// Address the output pays to, absent for non-standard scripts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    pub verbose_data: Option<ScriptVerboseData>,
+}
+
+/// Script class and address as reported by the node
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptVerboseData {
+    pub script_public_key_type: String,
+    pub script_public_key_address: String,
 }
 
 #[derive(Debug, Serialize)]
@@ -171,14 +429,489 @@ pub struct BlockVerboseData {
     pub transaction_ids: Vec<String>,
     pub is_header_only: bool,
     pub blue_score: u64,
+    pub children_hashes: Vec<String>,
+    pub merge_set_blues_hashes: Vec<String>,
+    pub merge_set_reds_hashes: Vec<String>,
     pub is_chain_block: bool,
 }
 
+impl TryFrom<&RpcBlock> for BlockResponse {
+    type Error = RpcError;
+
+    fn try_from(block: &RpcBlock) -> Result<Self, RpcError> {
+        let header = block.header.as_ref().ok_or_else(|| {
+            RpcError::InvalidResponse("Block header missing".into())
+        })?;
+
+        Ok(BlockResponse {
+            hash: header.hash.clone(),
+            header: BlockHeader::from(header),
+            transactions: block
+                .transactions
+                .iter()
+                .map(Transaction::from)
+                .collect(),
+            verbose_data: block.verbose_data.as_ref().map(BlockVerboseData::from),
+        })
+    }
+}
+
+impl From<&RpcBlockHeader> for BlockHeader {
+    fn from(header: &RpcBlockHeader) -> Self {
+        BlockHeader {
+            version: header.version,
+            parents: header
+                .parents
+                .iter()
+                .map(|level| BlockLevelParents {
+                    parent_hashes: level.parent_hashes.clone(),
+                })
+                .collect(),
+            hash_merkle_root: header.hash_merkle_root.clone(),
+            accepted_id_merkle_root: header.accepted_id_merkle_root.clone(),
+            utxo_commitment: header.utxo_commitment.clone(),
+            timestamp: header.timestamp,
+            bits: header.bits,
+            nonce: header.nonce,
+            daa_score: header.daa_score,
+            blue_work: header.blue_work.clone(),
+            blue_score: header.blue_score,
+            pruning_point: header.pruning_point.clone(),
+        }
+    }
+}
+
+impl From<&RpcBlockVerboseData> for BlockVerboseData {
+    fn from(vd: &RpcBlockVerboseData) -> Self {
+        BlockVerboseData {
+            hash: vd.hash.clone(),
+            difficulty: vd.difficulty,
+            selected_parent_hash: vd.selected_parent_hash.clone(),
+            transaction_ids: vd.transaction_ids.clone(),
+            is_header_only: vd.is_header_only,
+            blue_score: vd.blue_score,
+            children_hashes: vd.children_hashes.clone(),
+            merge_set_blues_hashes: vd.merge_set_blues_hashes.clone(),
+            merge_set_reds_hashes: vd.merge_set_reds_hashes.clone(),
+            is_chain_block: vd.is_chain_block,
+        }
+    }
+}
+
+impl From<&RpcTransaction> for Transaction {
+    fn from(tx: &RpcTransaction) -> Self {
+        let verbose = tx.verbose_data.as_ref();
+        Transaction {
+            transaction_id: verbose
+                .map(|v| v.transaction_id.clone())
+                .unwrap_or_default(),
+            hash: verbose.map(|v| v.hash.clone()).unwrap_or_default(),
+            version: tx.version,
+            mass: tx.mass,
+            inputs: tx.inputs.iter().map(TransactionInputVerbose::from).collect(),
+            outputs: tx.outputs.iter().map(TransactionOutput::from).collect(),
+            lock_time: tx.lock_time,
+            subnetwork_id: tx.subnetwork_id.clone(),
+            gas: tx.gas,
+            payload: tx.payload.clone(),
+            verbose_data: verbose.map(|v| TransactionVerboseData {
+                compute_mass: v.compute_mass,
+                block_hash: v.block_hash.clone(),
+                block_time: v.block_time,
+            }),
+        }
+    }
+}
+
+impl From<&RpcTransactionInput> for TransactionInputVerbose {
+    fn from(input: &RpcTransactionInput) -> Self {
+        let outpoint = input.previous_outpoint.as_ref();
+        TransactionInputVerbose {
+            previous_outpoint: OutpointVerbose {
+                transaction_id: outpoint
+                    .map(|o| o.transaction_id.clone())
+                    .unwrap_or_default(),
+                index: outpoint.map(|o| o.index).unwrap_or(0),
+            },
+            signature_script: input.signature_script.clone(),
+            sequence: input.sequence,
+            sig_op_count: input.sig_op_count,
+            verbose_data: input.verbose_data.as_ref().map(|v| TransactionInputVerboseData {
+                utxo_entry: v.utxo_entry.as_ref().map(UtxoEntry::from),
+            }),
+        }
+    }
+}
+
+impl From<&RpcUtxoEntry> for UtxoEntry {
+    fn from(entry: &RpcUtxoEntry) -> Self {
+        let script_pk = entry.script_public_key.clone().unwrap_or_default();
+        UtxoEntry {
+            amount: entry.amount,
+            script_public_key: script_pk.script_public_key,
+            script_version: script_pk.version,
+            block_daa_score: entry.block_daa_score,
+            is_coinbase: entry.is_coinbase,
+            verbose_data: entry.verbose_data.as_ref().map(|v| ScriptVerboseData {
+                script_public_key_type: v.script_public_key_type.clone(),
+                script_public_key_address: v.script_public_key_address.clone(),
+            }),
+        }
+    }
+}
+
+impl From<&RpcTransactionOutput> for TransactionOutput {
+    fn from(output: &RpcTransactionOutput) -> Self {
+        let script_pk = output.script_public_key.clone().unwrap_or_default();
+        let script = hex::decode(&script_pk.script_public_key).unwrap_or_default();
+        let (script_type, _) = ScriptType::classify(script_pk.version, &script);
+        let verbose_data = output.verbose_data.as_ref().map(|v| ScriptVerboseData {
+            script_public_key_type: v.script_public_key_type.clone(),
+            script_public_key_address: v.script_public_key_address.clone(),
+        });
+
+        // Without a configured network, trust the address the node sent
+        let address = match address::network() {
+            Some(prefix) => Address::from_script(script_pk.version, &script, prefix)
+                .map(|address| address.to_string()),
+            None => verbose_data
+                .as_ref()
+                .map(|v| v.script_public_key_address.clone())
+                .filter(|address| !address.is_empty()),
+        };
+
+        TransactionOutput {
+            amount: output.amount,
+            script_public_key: script_pk.script_public_key,
+            script_version: script_pk.version,
+            script_type: script_type.as_str(),
+            address,
+            verbose_data,
+        }
+    }
+}
+
+/// Blocks in request order
+#[derive(Debug, Serialize)]
+pub struct BlocksResponse {
+    pub blocks: Vec<BlockResult>,
+}
+
+/// One block of a batch, or why it could not be fetched
+#[derive(Debug, Serialize)]
+pub struct BlockResult {
+    pub hash: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block: Option<BlockResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ErrorBody>,
+}
+
+/// One page of the DAG above a low hash
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockRangeResponse {
+    pub block_hashes: Vec<String>,
+    /// Blocks matching `blockHashes`, when requested
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocks: Option<Vec<BlockResponse>>,
+    /// Where the next page starts, as `<chain block hash>:<offset>`;
+    /// unchanged once the virtual is reached
+    pub next_cursor: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct HeadersResponse {
+    pub headers: Vec<String>,
+}
+
+/// Virtual selected chain change (reorg) event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VirtualChainChanged {
+    pub removed_chain_block_hashes: Vec<String>,
+    pub added_chain_block_hashes: Vec<String>,
+    pub accepted_transaction_ids: Vec<AcceptedTransactionIds>,
+}
+
+impl From<&VirtualChainChangedNotificationMessage> for VirtualChainChanged {
+    fn from(notification: &VirtualChainChangedNotificationMessage) -> Self {
+        Self {
+            removed_chain_block_hashes: notification.removed_chain_block_hashes.clone(),
+            added_chain_block_hashes: notification.added_chain_block_hashes.clone(),
+            accepted_transaction_ids: notification
+                .accepted_transaction_ids
+                .iter()
+                .map(AcceptedTransactionIds::from)
+                .collect(),
+        }
+    }
+}
+
+impl From<GetVirtualChainFromBlockResponseMessage> for VirtualChainChanged {
+    fn from(response: GetVirtualChainFromBlockResponseMessage) -> Self {
+        Self {
+            removed_chain_block_hashes: response.removed_chain_block_hashes,
+            added_chain_block_hashes: response.added_chain_block_hashes,
+            accepted_transaction_ids: response
+                .accepted_transaction_ids
+                .iter()
+                .map(AcceptedTransactionIds::from)
+                .collect(),
+        }
+    }
+}
+
+/// Selected chain changes since a block, with where to resume
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VirtualChainFromBlockResponse {
+    #[serde(flatten)]
+    pub chain: VirtualChainChanged,
+    /// Last added chain block; pass back as `cursor` to poll for more
+    pub next_cursor: String,
+}
+
+/// Transactions accepted by a chain block
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AcceptedTransactionIds {
+    pub accepting_block_hash: String,
+    pub accepted_transaction_ids: Vec<String>,
+}
+
+impl From<&RpcAcceptedTransactionIds> for AcceptedTransactionIds {
+    fn from(accepted: &RpcAcceptedTransactionIds) -> Self {
+        Self {
+            accepting_block_hash: accepted.accepting_block_hash.clone(),
+            accepted_transaction_ids: accepted.accepted_transaction_ids.clone(),
+        }
+    }
+}
+
 /// Submit transaction response
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SubmitTransactionResponse {
     pub transaction_id: String,
+    pub transaction_hash: String,
+    /// Local mass estimate, without storage mass
+    pub estimated_mass: u64,
+    /// Whether the transaction was only validated, not broadcast
+    pub dry_run: bool,
+}
+
+/// Submitted replacement and the mempool transaction it evicted
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubmitTransactionReplacementResponse {
+    pub transaction_id: String,
+    /// Absent only if the replaced transaction fails to decode
+    pub replaced_transaction_id: Option<String>,
+    pub replaced_transaction: Transaction,
+}
+
+/// One page of UTXOs for a set of addresses
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UtxosResponse {
+    pub utxos: Vec<UtxoResponse>,
+    /// Cursor for the next page, absent on the last one
+    pub next_cursor: Option<String>,
+    /// Virtual DAA score maturity was judged against
+    pub virtual_daa_score: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UtxoResponse {
+    pub address: String,
+    pub outpoint: OutpointVerbose,
+    pub amount: u64,
+    pub script_public_key: String,
+    pub script_version: u32,
+    pub block_daa_score: u64,
+    pub is_coinbase: bool,
+    pub is_mature: bool,
+}
+
+/// Transaction waiting in the mempool
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MempoolEntryResponse {
+    pub transaction: Transaction,
+    /// Fee in sompi
+    pub fee: u64,
+    /// Fee in sompi per gram of mass
+    pub fee_rate: f64,
+    pub is_orphan: bool,
+}
+
+impl TryFrom<&RpcMempoolEntry> for MempoolEntryResponse {
+    type Error = RpcError;
+
+    fn try_from(entry: &RpcMempoolEntry) -> Result<Self, RpcError> {
+        let tx = entry.transaction.as_ref().ok_or_else(|| {
+            RpcError::InvalidResponse("Mempool transaction missing".into())
+        })?;
+
+        // Older nodes only report the compute mass in the verbose data
+        let mass = match tx.mass {
+            0 => tx.verbose_data.as_ref().map_or(0, |v| v.compute_mass),
+            mass => mass,
+        };
+        let fee_rate = if mass > 0 {
+            entry.fee as f64 / mass as f64
+        } else {
+            0.0
+        };
+
+        Ok(MempoolEntryResponse {
+            transaction: Transaction::from(tx),
+            fee: entry.fee,
+            fee_rate,
+            is_orphan: entry.is_orphan,
+        })
+    }
+}
+
+/// One page of mempool entries
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MempoolEntriesResponse {
+    pub entries: Vec<MempoolEntryResponse>,
+    /// Cursor for the next page, absent on the last one
+    pub next_cursor: Option<String>,
+    /// Entries matching the request across all pages
+    pub total: usize,
+}
+
+/// Mempool transactions spending from and paying to one address
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MempoolEntriesByAddressResponse {
+    pub address: String,
+    pub sending: Vec<MempoolEntryResponse>,
+    pub receiving: Vec<MempoolEntryResponse>,
+}
+
+/// Mempool transactions per requested address
+#[derive(Debug, Serialize)]
+pub struct MempoolEntriesByAddressesResponse {
+    pub entries: Vec<MempoolEntriesByAddressResponse>,
+}
+
+/// Fee rates per priority tier
+///
+/// Chaining `priority`, `normal` and `low` gives points on the node's
+/// fee rate to inclusion time curve, fastest first.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeEstimateResponse {
+    /// Estimate for sub-second inclusion
+    pub priority: FeeRateBucket,
+    /// Estimates for sub-minute inclusion
+    pub normal: Vec<FeeRateBucket>,
+    /// Estimates for sub-hour inclusion
+    pub low: Vec<FeeRateBucket>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verbose: Option<FeeEstimateVerbose>,
+}
+
+impl FeeEstimateResponse {
+    /// Convert the node's estimate, pricing each tier for `mass` if given
+    pub fn new(estimate: &RpcFeeEstimate, mass: Option<u64>) -> Self {
+        let bucket = |bucket: &RpcFeerateBucket| FeeRateBucket::new(bucket, mass);
+        FeeEstimateResponse {
+            priority: estimate
+                .priority_bucket
+                .as_ref()
+                .map(bucket)
+                .unwrap_or_else(|| bucket(&RpcFeerateBucket::default())),
+            normal: estimate.normal_buckets.iter().map(bucket).collect(),
+            low: estimate.low_buckets.iter().map(bucket).collect(),
+            verbose: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeRateBucket {
+    /// Fee in sompi per gram of mass
+    pub fee_rate: f64,
+    pub estimated_seconds: f64,
+    /// Fee in sompi for the requested mass
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fee: Option<u64>,
+}
+
+impl FeeRateBucket {
+    fn new(bucket: &RpcFeerateBucket, mass: Option<u64>) -> Self {
+        FeeRateBucket {
+            fee_rate: bucket.feerate,
+            estimated_seconds: bucket.estimated_seconds,
+            fee: mass.map(|mass| fee_for_mass(bucket.feerate, mass)),
+        }
+    }
+}
+
+/// Fee in sompi for a transaction of `mass` grams at `fee_rate` sompi per
+/// gram, rounded up so the transaction never falls below the rate
+pub fn fee_for_mass(fee_rate: f64, mass: u64) -> u64 {
+    (fee_rate * mass as f64).ceil() as u64
+}
+
+/// Experimental mempool data behind the estimate
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeEstimateVerbose {
+    pub mempool_ready_transactions_count: u64,
+    pub mempool_ready_transactions_total_mass: u64,
+    pub network_mass_per_second: u64,
+    pub next_block_template_fee_rate_min: f64,
+    pub next_block_template_fee_rate_median: f64,
+    pub next_block_template_fee_rate_max: f64,
+}
+
+impl From<&RpcFeeEstimateVerboseExperimentalData> for FeeEstimateVerbose {
+    fn from(data: &RpcFeeEstimateVerboseExperimentalData) -> Self {
+        FeeEstimateVerbose {
+            mempool_ready_transactions_count: data.mempool_ready_transactions_count,
+            mempool_ready_transactions_total_mass: data.mempool_ready_transactions_total_mass,
+            network_mass_per_second: data.network_mass_per_second,
+            next_block_template_fee_rate_min: data.next_block_template_feerate_min,
+            next_block_template_fee_rate_median: data.next_block_template_feerate_median,
+            next_block_template_fee_rate_max: data.next_block_template_feerate_max,
+        }
+    }
+}
+
+/// Balance of one address
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceResponse {
+    pub address: String,
+    /// Balance in sompi
+    pub balance: u64,
+    /// Balance in KAS as an exact decimal string
+    pub balance_kas: String,
+}
+
+impl BalanceResponse {
+    pub fn new(address: String, balance: u64) -> Self {
+        Self {
+            address,
+            balance,
+            balance_kas: crate::balances::sompi_to_kas(balance),
+        }
+    }
+}
+
+/// Balances in request order
+#[derive(Debug, Serialize)]
+pub struct BalancesResponse {
+    pub balances: Vec<BalanceResponse>,
 }
 
 /// DAG tips response
@@ -194,3 +927,197 @@ pub struct DAGTipsResponse {
     pub pruning_point_hash: String,
     pub virtual_daa_score: u64,
 }
+
+/// Node version, mempool size and capabilities
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InfoResponse {
+    pub p2p_id: String,
+    pub mempool_size: u64,
+    pub server_version: String,
+    pub is_utxo_indexed: bool,
+    pub is_synced: bool,
+}
+
+impl From<GetInfoResponseMessage> for InfoResponse {
+    fn from(info: GetInfoResponseMessage) -> Self {
+        InfoResponse {
+            p2p_id: info.p2p_id,
+            mempool_size: info.mempool_size,
+            server_version: info.server_version,
+            is_utxo_indexed: info.is_utxo_indexed,
+            is_synced: info.is_synced,
+        }
+    }
+}
+
+/// RPC API version, network and sync state
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerInfoResponse {
+    pub rpc_api_version: u32,
+    pub rpc_api_revision: u32,
+    pub server_version: String,
+    pub network_id: String,
+    pub has_utxo_index: bool,
+    pub is_synced: bool,
+    pub virtual_daa_score: u64,
+}
+
+impl From<GetServerInfoResponseMessage> for ServerInfoResponse {
+    fn from(info: GetServerInfoResponseMessage) -> Self {
+        ServerInfoResponse {
+            rpc_api_version: info.rpc_api_version,
+            rpc_api_revision: info.rpc_api_revision,
+            server_version: info.server_version,
+            network_id: info.network_id,
+            has_utxo_index: info.has_utxo_index,
+            is_synced: info.is_synced,
+            virtual_daa_score: info.virtual_daa_score,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncStatusResponse {
+    pub is_synced: bool,
+}
+
+impl From<GetSyncStatusResponseMessage> for SyncStatusResponse {
+    fn from(status: GetSyncStatusResponseMessage) -> Self {
+        SyncStatusResponse {
+            is_synced: status.is_synced,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentNetworkResponse {
+    pub current_network: String,
+}
+
+impl From<GetCurrentNetworkResponseMessage> for CurrentNetworkResponse {
+    fn from(network: GetCurrentNetworkResponseMessage) -> Self {
+        CurrentNetworkResponse {
+            current_network: network.current_network,
+        }
+    }
+}
+
+/// Coin supply in sompi and KAS
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CoinSupplyResponse {
+    /// Hard-coded maximum; the real maximum may be up to 5% lower
+    pub max_sompi: u64,
+    pub circulating_sompi: u64,
+    pub max_kas: String,
+    pub circulating_kas: String,
+}
+
+impl From<GetCoinSupplyResponseMessage> for CoinSupplyResponse {
+    fn from(supply: GetCoinSupplyResponseMessage) -> Self {
+        CoinSupplyResponse {
+            max_sompi: supply.max_sompi,
+            circulating_sompi: supply.circulating_sompi,
+            max_kas: crate::balances::sompi_to_kas(supply.max_sompi),
+            circulating_kas: crate::balances::sompi_to_kas(supply.circulating_sompi),
+        }
+    }
+}
+
+/// Everything a status page needs, gathered in one request
+///
+/// Sections whose call failed are absent and their error is listed in
+/// `errors` under the same key.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub info: Option<InfoResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub server_info: Option<ServerInfoResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sync_status: Option<SyncStatusResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub network: Option<CurrentNetworkResponse>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coin_supply: Option<CoinSupplyResponse>,
+    #[serde(skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    pub errors: std::collections::BTreeMap<&'static str, String>,
+}
+
+/// Health check response
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthResponse {
+    pub status: String,
+    pub upstreams: Vec<UpstreamStatus>,
+}
+
+/// Health of a single upstream node
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpstreamStatus {
+    pub url: String,
+    #[serde(flatten)]
+    pub health: UpstreamHealth,
+}
+
+/// Request to exchange an API key for a JWT
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenRequest {
+    pub api_key: String,
+}
+
+/// Issued JWT
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenResponse {
+    pub token: String,
+    pub role: String,
+    pub expires_in: usize,
+}
+
+/// Request to create an API key
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub role: String,
+}
+
+/// API key metadata, never including the secret or its hash
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyInfo {
+    pub id: String,
+    pub name: String,
+    pub role: String,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub revoked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<ApiKeyRecord> for ApiKeyInfo {
+    fn from(record: ApiKeyRecord) -> Self {
+        Self {
+            id: record.id,
+            name: record.name,
+            role: record.role,
+            created_at: record.created_at,
+            revoked_at: record.revoked_at,
+        }
+    }
+}
+
+/// Newly created API key; the plaintext key is only ever returned here
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub info: ApiKeyInfo,
+    pub api_key: String,
+}
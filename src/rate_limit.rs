@@ -0,0 +1,310 @@
+use crate::{auth::Claims, error::RpcError, metrics, AppState};
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::Extensions,
+    middleware::Next,
+    response::Response,
+};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How often idle buckets are dropped
+const BUCKET_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Cost charged for methods without a configured cost
+const DEFAULT_COST: f64 = 1.0;
+
+/// Method costs used when `RATE_LIMIT_COSTS` is not set
+pub const DEFAULT_COSTS: &str =
+    "submitTransaction=5,submitTransactionReplacement=5,getBlocks=10,token=10";
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Per-client token buckets with per-method costs
+///
+/// Every client gets a bucket of `burst` tokens refilled at `rate` tokens
+/// per second. A call is admitted when the bucket holds at least the
+/// method's cost, which is then deducted. A rate of zero disables
+/// limiting; otherwise every cost must fit into the burst.
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    costs: HashMap<String, f64>,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(rate: f64, burst: f64, costs: HashMap<String, f64>) -> anyhow::Result<Arc<Self>> {
+        if !rate.is_finite() || rate < 0.0 {
+            anyhow::bail!("RATE_LIMIT_RPS must be a non-negative number, got {}", rate);
+        }
+        if !burst.is_finite() || burst < 0.0 {
+            anyhow::bail!(
+                "RATE_LIMIT_BURST must be a non-negative number, got {}",
+                burst
+            );
+        }
+        // A call costing more than the burst could never be admitted
+        if rate > 0.0 {
+            let default = ("methods without a configured cost", &DEFAULT_COST);
+            let costs = costs.iter().map(|(method, cost)| (method.as_str(), cost));
+            for (method, cost) in costs.chain([default]) {
+                if *cost > burst {
+                    anyhow::bail!(
+                        "Cost {} of {} exceeds RATE_LIMIT_BURST {}",
+                        cost,
+                        method,
+                        burst
+                    );
+                }
+            }
+        }
+
+        let limiter = Arc::new(Self {
+            rate,
+            burst,
+            costs,
+            buckets: Mutex::new(HashMap::new()),
+        });
+
+        // Refilled buckets carry no state worth keeping
+        let sweeper = Arc::downgrade(&limiter);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(BUCKET_SWEEP_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let Some(limiter) = sweeper.upgrade() else {
+                    break;
+                };
+                limiter.sweep();
+            }
+        });
+
+        Ok(limiter)
+    }
+
+    /// Configured cost of a method
+    pub fn cost(&self, method: &str) -> f64 {
+        self.costs.get(method).copied().unwrap_or(DEFAULT_COST)
+    }
+
+    /// Charge `cost` to `client`, or report how long until it would fit
+    pub fn check(&self, client: &str, cost: f64) -> Result<(), Duration> {
+        if self.rate <= 0.0 {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+
+        let bucket = buckets.entry(client.to_string()).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= cost {
+            bucket.tokens -= cost;
+            return Ok(());
+        }
+
+        let missing = cost - bucket.tokens;
+        Err(Duration::from_secs_f64(missing / self.rate))
+    }
+
+    /// Charge a method call, recording the outcome in the metrics
+    pub fn charge(&self, client: &str, method: &str) -> Result<(), RpcError> {
+        let cost = self.cost(method);
+        match self.check(client, cost) {
+            Ok(()) => {
+                metrics::record_rate_limit_cost(method, cost);
+                Ok(())
+            }
+            Err(retry_after) => {
+                metrics::record_rate_limited(method);
+                Err(RpcError::RateLimited(
+                    retry_after.as_secs_f64().ceil().max(1.0) as u64,
+                ))
+            }
+        }
+    }
+
+    fn sweep(&self) {
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        buckets.retain(|_, bucket| {
+            let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+            bucket.tokens + elapsed * self.rate < self.burst
+        });
+        metrics::record_rate_limit_clients(buckets.len());
+    }
+}
+
+/// Middleware charging each request to its client's bucket
+///
+/// Clients are identified by their token subject (the API key for
+/// issued tokens), falling back to the peer IP address. The method is
+/// the last path segment, e.g. `getBlock` for `/rpc/getBlock`.
+pub async fn rate_limit(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> Result<Response, RpcError> {
+    let client = client_key(request.extensions());
+    let method = request
+        .uri()
+        .path()
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    state.rate_limiter.charge(&client, &method)?;
+
+    Ok(next.run(request).await)
+}
+
+/// Rate limit bucket key for a request, from its extensions
+pub fn client_key(extensions: &Extensions) -> String {
+    if let Some(claims) = extensions.get::<Claims>() {
+        return format!("sub:{}", claims.sub);
+    }
+
+    match extensions.get::<ConnectInfo<SocketAddr>>() {
+        Some(ConnectInfo(addr)) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+/// Parse `method=cost` pairs, e.g. `submitTransaction=5,getBlock=1`
+pub fn parse_costs(spec: &str) -> anyhow::Result<HashMap<String, f64>> {
+    spec.split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (method, cost) = pair
+                .split_once('=')
+                .ok_or_else(|| anyhow::anyhow!("Invalid method cost '{}'", pair))?;
+            let cost: f64 = cost
+                .trim()
+                .parse()
+                .ok()
+                .filter(|cost: &f64| cost.is_finite() && *cost >= 0.0)
+                .ok_or_else(|| anyhow::anyhow!("Invalid cost for method '{}'", method))?;
+            Ok((method.trim().to_string(), cost))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(rate: f64, burst: f64, costs: &str) -> Arc<RateLimiter> {
+        RateLimiter::new(rate, burst, parse_costs(costs).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn parses_costs() {
+        let costs = parse_costs(" submitTransaction=5, getBlock = 0.5 ,").unwrap();
+        assert_eq!(costs.len(), 2);
+        assert_eq!(costs["submitTransaction"], 5.0);
+        assert_eq!(costs["getBlock"], 0.5);
+        assert!(parse_costs("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_costs() {
+        for spec in [
+            "getBlock",
+            "getBlock=",
+            "getBlock=cheap",
+            "getBlock=NaN",
+            "getBlock=inf",
+            "getBlock=-1",
+        ] {
+            assert!(parse_costs(spec).is_err(), "{}", spec);
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_unusable_limits() {
+        assert!(RateLimiter::new(f64::NAN, 10.0, HashMap::new()).is_err());
+        assert!(RateLimiter::new(f64::INFINITY, 10.0, HashMap::new()).is_err());
+        assert!(RateLimiter::new(-1.0, 10.0, HashMap::new()).is_err());
+        assert!(RateLimiter::new(1.0, f64::NAN, HashMap::new()).is_err());
+        assert!(RateLimiter::new(1.0, -1.0, HashMap::new()).is_err());
+
+        // Costs above the burst, configured or default, could never pass
+        let costs = parse_costs("getBlocks=20").unwrap();
+        assert!(RateLimiter::new(1.0, 10.0, costs.clone()).is_err());
+        assert!(RateLimiter::new(1.0, 0.5, HashMap::new()).is_err());
+        // Unless limiting is off
+        assert!(RateLimiter::new(0.0, 10.0, costs).is_ok());
+    }
+
+    #[tokio::test]
+    async fn charges_costs_until_the_burst_is_spent() {
+        let limiter = limiter(1.0, 10.0, "getBlocks=4");
+        assert_eq!(limiter.cost("getBlocks"), 4.0);
+        assert_eq!(limiter.cost("getBlock"), DEFAULT_COST);
+
+        assert!(limiter.charge("a", "getBlocks").is_ok());
+        assert!(limiter.charge("a", "getBlocks").is_ok());
+        assert!(limiter.charge("a", "getBlock").is_ok());
+        assert!(limiter.charge("a", "getBlock").is_ok());
+        assert!(matches!(
+            limiter.charge("a", "getBlocks"),
+            Err(RpcError::RateLimited(_))
+        ));
+        // Buckets are per client
+        assert!(limiter.charge("b", "getBlocks").is_ok());
+    }
+
+    #[tokio::test]
+    async fn reports_time_until_the_cost_fits() {
+        let limiter = limiter(2.0, 10.0, "");
+        assert!(limiter.check("a", 10.0).is_ok());
+
+        let retry_after = limiter.check("a", 4.0).unwrap_err();
+        assert!(retry_after <= Duration::from_secs(2));
+        assert!(retry_after > Duration::from_millis(1900));
+        // Retry-After is rounded up to whole seconds
+        assert!(matches!(
+            limiter.charge("a", "getBlock"),
+            Err(RpcError::RateLimited(1))
+        ));
+    }
+
+    #[tokio::test]
+    async fn zero_rate_disables_limiting() {
+        let limiter = limiter(0.0, 0.0, "");
+        for _ in 0..100 {
+            assert!(limiter.charge("a", "getBlock").is_ok());
+        }
+        assert!(limiter.buckets.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn sweep_drops_refilled_buckets() {
+        let limiter = limiter(1000.0, 10.0, "");
+        assert!(limiter.check("refilled", 5.0).is_ok());
+        std::thread::sleep(Duration::from_millis(20));
+
+        let slow = RateLimiter::new(0.001, 10.0, HashMap::new()).unwrap();
+        assert!(slow.check("drained", 5.0).is_ok());
+
+        limiter.sweep();
+        slow.sweep();
+        assert!(limiter.buckets.lock().unwrap().is_empty());
+        assert_eq!(slow.buckets.lock().unwrap().len(), 1);
+    }
+}
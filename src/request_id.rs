@@ -0,0 +1,58 @@
+use axum::{extract::Request, http::HeaderValue, middleware::Next, response::Response};
+
+/// Header carrying the request ID, read from clients and echoed back
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied ID that is kept rather than replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static CURRENT: RequestId;
+}
+
+/// ID of one HTTP request, for matching client reports to server logs
+#[derive(Debug, Clone)]
+pub struct RequestId(pub String);
+
+/// Tag every request with an ID and echo it in the `x-request-id` header
+///
+/// A well-formed ID sent by the client is kept so calls can be traced
+/// across services; otherwise a random one is generated.
+pub async fn assign(mut request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let request_id = RequestId(id);
+    request.extensions_mut().insert(request_id.clone());
+
+    // Errors are rendered inside the scope, so they can pick up the ID
+    let mut response = CURRENT.scope(request_id.clone(), next.run(request)).await;
+    if let Ok(value) = HeaderValue::from_str(&request_id.0) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Run `future` as part of request `id`, e.g. a WebSocket session that
+/// outlives the upgrade request
+pub async fn scope<F: std::future::Future>(id: RequestId, future: F) -> F::Output {
+    CURRENT.scope(id, future).await
+}
+
+/// ID of the request being served on this task, if any
+pub fn current() -> Option<String> {
+    CURRENT.try_with(|id| id.0.clone()).ok()
+}
+
+fn is_valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
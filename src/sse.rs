@@ -0,0 +1,55 @@
+use crate::{
+    error::RpcError,
+    subscriptions::{Event, Topic},
+    AppState,
+};
+use axum::{
+    extract::State,
+    response::sse::{self, KeepAlive, Sse},
+};
+use std::convert::Infallible;
+use tokio_stream::{Stream, StreamExt};
+
+/// Server-Sent Events stream of sink blue score changes
+pub async fn sink_blue_score(
+    State(state): State<AppState>,
+) -> Result<Sse<impl Stream<Item = Result<sse::Event, Infallible>>>, RpcError> {
+    stream_topic(&state, Topic::SinkBlueScoreChanged).await
+}
+
+/// Server-Sent Events stream of virtual DAA score changes
+pub async fn virtual_daa_score(
+    State(state): State<AppState>,
+) -> Result<Sse<impl Stream<Item = Result<sse::Event, Infallible>>>, RpcError> {
+    stream_topic(&state, Topic::VirtualDaaScoreChanged).await
+}
+
+/// Join the shared subscription for `topic` and relay it as SSE events
+///
+/// The stream ends when the client goes away, which drops the
+/// subscription and releases its upstream registration.
+async fn stream_topic(
+    state: &AppState,
+    topic: Topic,
+) -> Result<Sse<impl Stream<Item = Result<sse::Event, Infallible>>>, RpcError> {
+    tracing::info!("New SSE subscription for {:?}", topic);
+
+    let subscription = state.subscriptions.subscribe(topic, Vec::new()).await?;
+    let events = subscription.filter_map(|event| {
+        let event = match event {
+            Event::SinkBlueScoreChanged(sink_blue_score) => sse::Event::default()
+                .event("sink_blue_score_changed")
+                .data(serde_json::json!({ "sinkBlueScore": sink_blue_score }).to_string()),
+            Event::VirtualDaaScoreChanged(virtual_daa_score) => sse::Event::default()
+                .event("virtual_daa_score_changed")
+                .data(serde_json::json!({ "virtualDaaScore": virtual_daa_score }).to_string()),
+            Event::Resync(dropped) => sse::Event::default()
+                .event("resync")
+                .data(serde_json::json!({ "dropped": dropped }).to_string()),
+            _ => return None,
+        };
+        Some(Ok(event))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,759 @@
+use crate::{
+    balances::BalanceCache,
+    client::{
+        proto::{kaspad_response::Payload, RpcUtxosByAddressesEntry},
+        KaspaClient, Notification,
+    },
+    error::RpcError,
+    metrics,
+    models::{BlockResponse, VirtualChainChanged},
+    upstream::UpstreamPool,
+};
+use std::collections::{HashMap, HashSet};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, RwLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{broadcast::error::RecvError, mpsc, watch};
+use tokio_stream::Stream;
+
+/// Events buffered per subscriber before it is considered too slow
+///
+/// A subscriber that falls this far behind misses events until it has
+/// drained its buffer, then receives `Event::Resync`.
+const SUBSCRIBER_CHANNEL_CAPACITY: usize = 1024;
+
+/// How often to check whether notifications should move to another node
+const MIGRATION_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Notification feed a subscriber listens to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topic {
+    UtxosChanged,
+    BlockAdded,
+    VirtualChainChanged {
+        include_accepted_transaction_ids: bool,
+    },
+    SinkBlueScoreChanged,
+    VirtualDaaScoreChanged,
+}
+
+impl Topic {
+    /// Name used for metrics labels
+    pub fn name(&self) -> &'static str {
+        match self {
+            Topic::UtxosChanged => "utxos_changed",
+            Topic::BlockAdded => "block_added",
+            Topic::VirtualChainChanged { .. } => "virtual_chain_changed",
+            Topic::SinkBlueScoreChanged => "sink_blue_score_changed",
+            Topic::VirtualDaaScoreChanged => "virtual_daa_score_changed",
+        }
+    }
+}
+
+/// Event delivered to a single subscriber
+#[derive(Debug)]
+pub enum Event {
+    UtxosChanged(UtxoChange),
+    BlockAdded(Arc<BlockResponse>),
+    VirtualChainChanged(Arc<VirtualChainChanged>),
+    SinkBlueScoreChanged(u64),
+    VirtualDaaScoreChanged(u64),
+    /// This many events were dropped because the subscriber or the
+    /// service fell behind, or 0 when notifications moved to another node
+    /// and the count is unknown; anything derived from earlier events
+    /// should be refetched
+    Resync(u64),
+}
+
+/// UTXO changes for the addresses a single subscriber watches
+#[derive(Debug, Default)]
+pub struct UtxoChange {
+    pub added: Vec<RpcUtxosByAddressesEntry>,
+    pub removed: Vec<RpcUtxosByAddressesEntry>,
+}
+
+/// Address-independent registrations currently held upstream
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct TopicRegistrations {
+    block_added: bool,
+    // Whether accepted transaction IDs are included, when registered
+    virtual_chain_changed: Option<bool>,
+    sink_blue_score_changed: bool,
+    virtual_daa_score_changed: bool,
+}
+
+struct Subscriber {
+    topic: Topic,
+    addresses: HashSet<String>,
+    sender: mpsc::Sender<Event>,
+    // Events dropped since the subscriber was last told to resync, while
+    // a resync is pending
+    dropped: Arc<Mutex<Option<u64>>>,
+}
+
+impl Subscriber {
+    fn deliver(&self, id: u64, event: Event) {
+        let mut dropped = self.dropped.lock().unwrap();
+        // Newer events wait for the resync, so none arrive out of order
+        if dropped.is_none() {
+            match self.sender.try_send(event) {
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    tracing::warn!(subscriber = id, "Subscriber too slow, dropping events");
+                }
+                Ok(()) | Err(mpsc::error::TrySendError::Closed(_)) => return,
+            }
+        }
+        *dropped = Some(dropped.unwrap_or(0) + 1);
+        metrics::record_subscriber_event_dropped(self.topic.name());
+    }
+
+    /// Tell the subscriber `missed` events never reached the service
+    fn resync(&self, missed: u64) {
+        let mut dropped = self.dropped.lock().unwrap();
+        if dropped.is_none() {
+            match self.sender.try_send(Event::Resync(missed)) {
+                Err(mpsc::error::TrySendError::Full(_)) => {}
+                Ok(()) | Err(mpsc::error::TrySendError::Closed(_)) => return,
+            }
+        }
+        // A full buffer is read eventually, and the resync follows it
+        *dropped = Some(dropped.unwrap_or(0) + missed);
+    }
+}
+
+#[derive(Default)]
+struct Subscribers {
+    next_id: u64,
+    by_id: HashMap<u64, Subscriber>,
+    by_address: HashMap<String, HashSet<u64>>,
+}
+
+impl Subscribers {
+    /// Add addresses to a subscriber, returning those new to the upstream set
+    fn watch(&mut self, id: u64, addresses: &[String]) -> Vec<String> {
+        let mut added = Vec::new();
+        let Some(subscriber) = self.by_id.get_mut(&id) else {
+            return added;
+        };
+
+        for address in addresses {
+            if !subscriber.addresses.insert(address.clone()) {
+                continue;
+            }
+            let watchers = self.by_address.entry(address.clone()).or_default();
+            if watchers.is_empty() {
+                added.push(address.clone());
+            }
+            watchers.insert(id);
+        }
+        added
+    }
+
+    /// Remove addresses from a subscriber, returning those no longer watched by anyone
+    fn unwatch(&mut self, id: u64, addresses: &[String]) -> Vec<String> {
+        let mut removed = Vec::new();
+        let Some(subscriber) = self.by_id.get_mut(&id) else {
+            return removed;
+        };
+
+        for address in addresses {
+            if !subscriber.addresses.remove(address) {
+                continue;
+            }
+            if let Some(watchers) = self.by_address.get_mut(address) {
+                watchers.remove(&id);
+                if watchers.is_empty() {
+                    self.by_address.remove(address);
+                    removed.push(address.clone());
+                }
+            }
+        }
+        removed
+    }
+
+    /// Topic registrations needed to serve every current subscriber
+    fn desired_topics(&self) -> TopicRegistrations {
+        let mut desired = TopicRegistrations::default();
+        for subscriber in self.by_id.values() {
+            match subscriber.topic {
+                Topic::UtxosChanged => {}
+                Topic::BlockAdded => desired.block_added = true,
+                Topic::VirtualChainChanged {
+                    include_accepted_transaction_ids,
+                } => {
+                    let include = desired.virtual_chain_changed.unwrap_or(false);
+                    desired.virtual_chain_changed =
+                        Some(include || include_accepted_transaction_ids);
+                }
+                Topic::SinkBlueScoreChanged => desired.sink_blue_score_changed = true,
+                Topic::VirtualDaaScoreChanged => desired.virtual_daa_score_changed = true,
+            }
+        }
+        desired
+    }
+
+    fn subscribed_to(
+        &self,
+        matches: impl Fn(Topic) -> bool,
+    ) -> impl Iterator<Item = (&u64, &Subscriber)> {
+        self.by_id
+            .iter()
+            .filter(move |(_, subscriber)| matches(subscriber.topic))
+    }
+
+    /// Tell every subscriber to refetch, clearing cached balances when
+    /// UTXO changes may have been missed
+    fn resync(&self, missed: u64, balances: &BalanceCache) {
+        if !self.by_address.is_empty() {
+            balances.clear();
+        }
+        for subscriber in self.by_id.values() {
+            subscriber.resync(missed);
+        }
+    }
+
+    fn record_metrics(&self) {
+        let utxo_subscribers = self
+            .subscribed_to(|topic| topic == Topic::UtxosChanged)
+            .count();
+        metrics::record_utxo_subscriptions(utxo_subscribers, self.by_address.len());
+    }
+}
+
+/// Shares one set of upstream notification registrations between all clients
+///
+/// The upstream UTXO address set is the union of every subscriber's
+/// addresses, reference counted so it grows and shrinks as clients come
+/// and go. Address-independent topics (blocks, chain changes, score
+/// ticks) are registered while at least one client listens. Incoming
+/// notifications are converted once and fanned out to the interested
+/// subscribers.
+pub struct SubscriptionManager {
+    upstreams: Arc<UpstreamPool>,
+    balances: Arc<BalanceCache>,
+    current: RwLock<Arc<KaspaClient>>,
+    subscribers: Mutex<Subscribers>,
+    // Serializes upstream registration changes so starts and stops stay ordered
+    registration: tokio::sync::Mutex<TopicRegistrations>,
+    connected: watch::Sender<bool>,
+}
+
+impl SubscriptionManager {
+    pub fn new(upstreams: Arc<UpstreamPool>, balances: Arc<BalanceCache>) -> Arc<Self> {
+        let current = upstreams.select();
+        let (connected, _) = watch::channel(current.is_connected());
+
+        let manager = Arc::new(Self {
+            upstreams,
+            balances,
+            current: RwLock::new(current),
+            subscribers: Mutex::new(Subscribers::default()),
+            registration: tokio::sync::Mutex::new(TopicRegistrations::default()),
+            connected,
+        });
+
+        tokio::spawn(manager.clone().run());
+        manager
+    }
+
+    /// Watch whether the node serving notifications is connected
+    pub fn connection_state(&self) -> watch::Receiver<bool> {
+        self.connected.subscribe()
+    }
+
+    /// Register a new subscriber; `addresses` only apply to UTXO changes
+    pub async fn subscribe(
+        self: &Arc<Self>,
+        topic: Topic,
+        addresses: Vec<String>,
+    ) -> Result<Subscription, RpcError> {
+        let (sender, receiver) = mpsc::channel(SUBSCRIBER_CHANNEL_CAPACITY);
+        let dropped = Arc::new(Mutex::new(None));
+
+        let id = {
+            let mut subscribers = self.subscribers.lock().unwrap();
+            subscribers.next_id += 1;
+            let id = subscribers.next_id;
+            subscribers.by_id.insert(
+                id,
+                Subscriber {
+                    topic,
+                    addresses: HashSet::new(),
+                    sender,
+                    dropped: dropped.clone(),
+                },
+            );
+            id
+        };
+
+        // Dropping the handle on failure undoes the registration
+        let subscription = Subscription {
+            id,
+            receiver,
+            dropped,
+            manager: self.clone(),
+        };
+
+        match topic {
+            Topic::UtxosChanged => self.add_addresses(id, addresses).await?,
+            _ => self.sync_topics().await?,
+        }
+
+        Ok(subscription)
+    }
+
+    /// Start watching more addresses for an existing subscriber
+    async fn add_addresses(&self, id: u64, addresses: Vec<String>) -> Result<(), RpcError> {
+        let _registration = self.registration.lock().await;
+
+        let (fresh, added) = {
+            let mut subscribers = self.subscribers.lock().unwrap();
+            let fresh: Vec<String> = match subscribers.by_id.get(&id) {
+                Some(subscriber) if subscriber.topic != Topic::UtxosChanged => {
+                    return Err(RpcError::BadRequest(
+                        "Subscription does not take addresses".into(),
+                    ));
+                }
+                Some(subscriber) => addresses
+                    .iter()
+                    .filter(|address| !subscriber.addresses.contains(*address))
+                    .cloned()
+                    .collect(),
+                None => Vec::new(),
+            };
+            let added = subscribers.watch(id, &fresh);
+            subscribers.record_metrics();
+            (fresh, added)
+        };
+        if added.is_empty() {
+            return Ok(());
+        }
+
+        let client = self.current.read().unwrap().clone();
+        if let Err(e) = client.subscribe_utxo_changes(added).await {
+            // Roll back so the upstream set matches what the node accepted
+            let mut subscribers = self.subscribers.lock().unwrap();
+            subscribers.unwatch(id, &fresh);
+            subscribers.record_metrics();
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// Stop watching addresses for an existing subscriber
+    async fn remove_addresses(&self, id: u64, addresses: Vec<String>) -> Result<(), RpcError> {
+        let _registration = self.registration.lock().await;
+
+        let removed = {
+            let mut subscribers = self.subscribers.lock().unwrap();
+            if let Some(subscriber) = subscribers.by_id.get(&id) {
+                if subscriber.topic != Topic::UtxosChanged {
+                    return Err(RpcError::BadRequest(
+                        "Subscription does not take addresses".into(),
+                    ));
+                }
+            }
+            let removed = subscribers.unwatch(id, &addresses);
+            subscribers.record_metrics();
+            removed
+        };
+        if removed.is_empty() {
+            return Ok(());
+        }
+
+        let client = self.current.read().unwrap().clone();
+        client.unsubscribe_utxo_changes(removed).await
+    }
+
+    /// Bring upstream topic registrations in line with current subscribers
+    async fn sync_topics(&self) -> Result<(), RpcError> {
+        let mut registered = self.registration.lock().await;
+        let desired = self.subscribers.lock().unwrap().desired_topics();
+        let client = self.current.read().unwrap().clone();
+        apply_topics(&client, &mut registered, desired).await
+    }
+
+    /// Drop a subscriber and release its registrations upstream
+    async fn unsubscribe(&self, id: u64) {
+        let (topic, addresses): (Topic, Vec<String>) = {
+            let subscribers = self.subscribers.lock().unwrap();
+            match subscribers.by_id.get(&id) {
+                Some(subscriber) => (
+                    subscriber.topic,
+                    subscriber.addresses.iter().cloned().collect(),
+                ),
+                None => return,
+            }
+        };
+
+        if let Err(e) = self.remove_addresses(id, addresses).await {
+            tracing::warn!("Failed to release UTXO subscription: {}", e);
+        }
+
+        {
+            let mut subscribers = self.subscribers.lock().unwrap();
+            subscribers.by_id.remove(&id);
+            subscribers.record_metrics();
+        }
+
+        if topic != Topic::UtxosChanged {
+            if let Err(e) = self.sync_topics().await {
+                tracing::warn!("Failed to release subscription: {}", e);
+            }
+        }
+    }
+
+    /// Dispatch notifications from the current node, migrating when it goes down
+    async fn run(self: Arc<Self>) {
+        let mut migration_check = tokio::time::interval(MIGRATION_CHECK_INTERVAL);
+
+        loop {
+            let client = self.current.read().unwrap().clone();
+            let mut notifications = client.notifications();
+            let mut connection = client.connection_state();
+            self.connected.send_replace(*connection.borrow_and_update());
+
+            loop {
+                tokio::select! {
+                    notification = notifications.recv() => match notification {
+                        Ok(notification) => self.dispatch(&notification),
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::warn!(skipped, "Subscription manager lagging, notifications dropped");
+                            self.subscribers.lock().unwrap().resync(skipped, &self.balances);
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                    changed = connection.changed() => {
+                        if changed.is_err() {
+                            break;
+                        }
+                        self.connected.send_replace(*connection.borrow_and_update());
+                    },
+                    _ = migration_check.tick() => {
+                        if self.migrate_if_needed().await {
+                            break;
+                        }
+                    },
+                }
+            }
+        }
+    }
+
+    /// Move upstream registrations to a connected node if the current one
+    /// is down or on the wrong network
+    ///
+    /// Registrations stay with the current node until the candidate has
+    /// accepted all of them, so a failed move is retried on the next check.
+    async fn migrate_if_needed(&self) -> bool {
+        let current = self.current.read().unwrap().clone();
+        if current.is_connected() && self.upstreams.serves(&current) {
+            return false;
+        }
+
+        let candidate = self.upstreams.select();
+        if Arc::ptr_eq(&candidate, &current) || !candidate.is_connected() {
+            return false;
+        }
+
+        let mut registered = self.registration.lock().await;
+        let (addresses, desired) = {
+            let subscribers = self.subscribers.lock().unwrap();
+            let addresses: Vec<String> = subscribers.by_address.keys().cloned().collect();
+            (addresses, subscribers.desired_topics())
+        };
+
+        tracing::info!(
+            "Moving notifications for {} addresses to another node",
+            addresses.len()
+        );
+
+        let mut moved = TopicRegistrations::default();
+        let result = async {
+            if !addresses.is_empty() {
+                candidate.subscribe_utxo_changes(addresses.clone()).await?;
+            }
+            apply_topics(&candidate, &mut moved, desired).await
+        };
+        if let Err(e) = result.await {
+            tracing::error!(
+                "Failed to move notifications, keeping the current node: {}",
+                e
+            );
+            // Undo what the candidate accepted so it holds no stray registrations
+            if !addresses.is_empty() {
+                let _ = candidate.unsubscribe_utxo_changes(addresses).await;
+            }
+            let _ = apply_topics(&candidate, &mut moved, TopicRegistrations::default()).await;
+            return false;
+        }
+
+        // Keep the old node from replaying registrations once it reconnects
+        if !addresses.is_empty() {
+            let _ = current.unsubscribe_utxo_changes(addresses).await;
+        }
+        let mut stale = *registered;
+        let _ = apply_topics(&current, &mut stale, TopicRegistrations::default()).await;
+        *registered = moved;
+
+        *self.current.write().unwrap() = candidate;
+        // Notifications sent while the old node was down are lost
+        self.subscribers.lock().unwrap().resync(0, &self.balances);
+        true
+    }
+
+    /// Convert a notification once and hand each subscriber its share
+    fn dispatch(&self, notification: &Notification) {
+        match notification.as_ref() {
+            Payload::UtxosChangedNotification(notification) => {
+                let subscribers = self.subscribers.lock().unwrap();
+                let mut batches: HashMap<u64, UtxoChange> = HashMap::new();
+
+                for entry in &notification.added {
+                    for id in subscribers
+                        .by_address
+                        .get(&entry.address)
+                        .into_iter()
+                        .flatten()
+                    {
+                        batches.entry(*id).or_default().added.push(entry.clone());
+                    }
+                }
+                for entry in &notification.removed {
+                    for id in subscribers
+                        .by_address
+                        .get(&entry.address)
+                        .into_iter()
+                        .flatten()
+                    {
+                        batches.entry(*id).or_default().removed.push(entry.clone());
+                    }
+                }
+
+                for (id, change) in batches {
+                    if let Some(subscriber) = subscribers.by_id.get(&id) {
+                        subscriber.deliver(id, Event::UtxosChanged(change));
+                    }
+                }
+            }
+            Payload::BlockAddedNotification(notification) => {
+                let subscribers = self.subscribers.lock().unwrap();
+                let mut listeners = subscribers
+                    .subscribed_to(|topic| topic == Topic::BlockAdded)
+                    .peekable();
+                if listeners.peek().is_none() {
+                    return;
+                }
+
+                let block = notification
+                    .block
+                    .as_ref()
+                    .ok_or_else(|| RpcError::InvalidResponse("Block data missing".into()))
+                    .and_then(BlockResponse::try_from);
+                let block = match block {
+                    Ok(block) => Arc::new(block),
+                    Err(e) => {
+                        tracing::warn!("Dropping block-added notification: {}", e);
+                        return;
+                    }
+                };
+
+                for (id, subscriber) in listeners {
+                    subscriber.deliver(*id, Event::BlockAdded(block.clone()));
+                }
+            }
+            Payload::VirtualChainChangedNotification(notification) => {
+                let subscribers = self.subscribers.lock().unwrap();
+                let full = Arc::new(VirtualChainChanged::from(notification));
+                let mut without_ids = None;
+
+                let listeners = subscribers
+                    .subscribed_to(|topic| matches!(topic, Topic::VirtualChainChanged { .. }));
+                for (id, subscriber) in listeners {
+                    let event = match subscriber.topic {
+                        Topic::VirtualChainChanged {
+                            include_accepted_transaction_ids: false,
+                        } => without_ids
+                            .get_or_insert_with(|| {
+                                Arc::new(VirtualChainChanged {
+                                    accepted_transaction_ids: Vec::new(),
+                                    ..full.as_ref().clone()
+                                })
+                            })
+                            .clone(),
+                        _ => full.clone(),
+                    };
+                    subscriber.deliver(*id, Event::VirtualChainChanged(event));
+                }
+            }
+            Payload::SinkBlueScoreChangedNotification(notification) => {
+                let subscribers = self.subscribers.lock().unwrap();
+                for (id, subscriber) in
+                    subscribers.subscribed_to(|topic| topic == Topic::SinkBlueScoreChanged)
+                {
+                    let event = Event::SinkBlueScoreChanged(notification.sink_blue_score);
+                    subscriber.deliver(*id, event);
+                }
+            }
+            Payload::VirtualDaaScoreChangedNotification(notification) => {
+                let subscribers = self.subscribers.lock().unwrap();
+                for (id, subscriber) in
+                    subscribers.subscribed_to(|topic| topic == Topic::VirtualDaaScoreChanged)
+                {
+                    let event = Event::VirtualDaaScoreChanged(notification.virtual_daa_score);
+                    subscriber.deliver(*id, event);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Issue the registration changes that take `registered` to `desired`
+async fn apply_topics(
+    client: &KaspaClient,
+    registered: &mut TopicRegistrations,
+    desired: TopicRegistrations,
+) -> Result<(), RpcError> {
+    if desired.block_added != registered.block_added {
+        if desired.block_added {
+            client.subscribe_block_added().await?;
+        } else {
+            client.unsubscribe_block_added().await?;
+        }
+        registered.block_added = desired.block_added;
+    }
+
+    if desired.virtual_chain_changed != registered.virtual_chain_changed {
+        match desired.virtual_chain_changed {
+            Some(include_accepted_transaction_ids) => {
+                client
+                    .subscribe_virtual_chain_changed(include_accepted_transaction_ids)
+                    .await?
+            }
+            None => client.unsubscribe_virtual_chain_changed().await?,
+        }
+        registered.virtual_chain_changed = desired.virtual_chain_changed;
+    }
+
+    if desired.sink_blue_score_changed != registered.sink_blue_score_changed {
+        if desired.sink_blue_score_changed {
+            client.subscribe_sink_blue_score_changed().await?;
+        } else {
+            client.unsubscribe_sink_blue_score_changed().await?;
+        }
+        registered.sink_blue_score_changed = desired.sink_blue_score_changed;
+    }
+
+    if desired.virtual_daa_score_changed != registered.virtual_daa_score_changed {
+        if desired.virtual_daa_score_changed {
+            client.subscribe_virtual_daa_score_changed().await?;
+        } else {
+            client.unsubscribe_virtual_daa_score_changed().await?;
+        }
+        registered.virtual_daa_score_changed = desired.virtual_daa_score_changed;
+    }
+
+    Ok(())
+}
+
+/// A subscriber's handle; dropping it releases its registrations
+pub struct Subscription {
+    id: u64,
+    receiver: mpsc::Receiver<Event>,
+    dropped: Arc<Mutex<Option<u64>>>,
+    manager: Arc<SubscriptionManager>,
+}
+
+impl Subscription {
+    /// Wait for the next event
+    pub async fn recv(&mut self) -> Option<Event> {
+        std::future::poll_fn(|cx| Pin::new(&mut *self).poll_next(cx)).await
+    }
+
+    /// Start watching more addresses (UTXO subscriptions only)
+    pub async fn add_addresses(&self, addresses: Vec<String>) -> Result<(), RpcError> {
+        self.manager.add_addresses(self.id, addresses).await
+    }
+
+    /// Stop watching addresses (UTXO subscriptions only)
+    pub async fn remove_addresses(&self, addresses: Vec<String>) -> Result<(), RpcError> {
+        self.manager.remove_addresses(self.id, addresses).await
+    }
+}
+
+impl Stream for Subscription {
+    type Item = Event;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Event>> {
+        match self.receiver.poll_recv(cx) {
+            // Once the buffer has drained, the gap follows what was delivered
+            Poll::Pending => match self.dropped.lock().unwrap().take() {
+                None => Poll::Pending,
+                Some(dropped) => Poll::Ready(Some(Event::Resync(dropped))),
+            },
+            ready => ready,
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let manager = self.manager.clone();
+        let id = self.id;
+        tokio::spawn(async move { manager.unsubscribe(id).await });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscriber(capacity: usize) -> (Subscriber, mpsc::Receiver<Event>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        let subscriber = Subscriber {
+            topic: Topic::SinkBlueScoreChanged,
+            addresses: HashSet::new(),
+            sender,
+            dropped: Arc::new(Mutex::new(None)),
+        };
+        (subscriber, receiver)
+    }
+
+    #[test]
+    fn resync_is_sent_right_away_when_the_buffer_has_room() {
+        let (subscriber, mut receiver) = subscriber(4);
+        subscriber.resync(0);
+
+        assert!(matches!(receiver.try_recv(), Ok(Event::Resync(0))));
+        assert_eq!(*subscriber.dropped.lock().unwrap(), None);
+    }
+
+    #[test]
+    fn resync_waits_behind_a_full_buffer() {
+        let (subscriber, mut receiver) = subscriber(1);
+        subscriber.deliver(1, Event::SinkBlueScoreChanged(1));
+        subscriber.deliver(1, Event::SinkBlueScoreChanged(2));
+        subscriber.resync(5);
+
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(Event::SinkBlueScoreChanged(1))
+        ));
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(*subscriber.dropped.lock().unwrap(), Some(6));
+    }
+
+    #[test]
+    fn events_after_a_pending_resync_are_dropped() {
+        let (subscriber, mut receiver) = subscriber(1);
+        subscriber.deliver(1, Event::SinkBlueScoreChanged(1));
+        subscriber.resync(0);
+        let _ = receiver.try_recv();
+
+        // The gap has not been reported yet, so nothing may overtake it
+        subscriber.deliver(1, Event::SinkBlueScoreChanged(2));
+        assert!(receiver.try_recv().is_err());
+        assert_eq!(*subscriber.dropped.lock().unwrap(), Some(1));
+    }
+}
@@ -0,0 +1,534 @@
+use crate::{
+    balances::SOMPI_PER_KAS,
+    client::proto::{
+        RpcOutpoint, RpcScriptPublicKey, RpcTransaction, RpcTransactionInput, RpcTransactionOutput,
+    },
+    error::{FieldError, RpcError},
+};
+use blake2b_simd::{Params, State};
+use std::collections::HashMap;
+
+const HASH_SIZE: usize = 32;
+const SUBNETWORK_ID_SIZE: usize = 20;
+
+/// Only version 0 transactions are relayed
+const MAX_TRANSACTION_VERSION: u16 = 0;
+
+/// Outputs with a newer script version are non-standard and not relayed
+const MAX_SCRIPT_PUBLIC_KEY_VERSION: u16 = 0;
+
+/// Upper bound on the sum of a transaction's outputs
+const MAX_SOMPI: u64 = 29_000_000_000 * SOMPI_PER_KAS;
+
+const NATIVE_SUBNETWORK_ID: [u8; SUBNETWORK_ID_SIZE] = [0; SUBNETWORK_ID_SIZE];
+const COINBASE_SUBNETWORK_ID: [u8; SUBNETWORK_ID_SIZE] = {
+    let mut id = [0; SUBNETWORK_ID_SIZE];
+    id[0] = 1;
+    id
+};
+
+// Mass parameters of the node's mass calculator
+const MASS_PER_TX_BYTE: u64 = 1;
+const MASS_PER_SCRIPT_PUB_KEY_BYTE: u64 = 10;
+const MASS_PER_SIG_OP: u64 = 1000;
+const TRANSIENT_BYTE_TO_MASS_FACTOR: u64 = 4;
+
+struct Input {
+    transaction_id: [u8; HASH_SIZE],
+    index: u32,
+    signature_script: Vec<u8>,
+    sequence: u64,
+    sig_op_count: u8,
+}
+
+struct Output {
+    amount: u64,
+    script_version: u16,
+    script: Vec<u8>,
+}
+
+/// A transaction whose fields all decoded and passed the node's
+/// stateless checks
+///
+/// Computes the transaction ID, hash and mass the same way the node
+/// does, so clients learn them before anything is broadcast.
+pub struct ValidatedTransaction {
+    version: u16,
+    inputs: Vec<Input>,
+    outputs: Vec<Output>,
+    lock_time: u64,
+    subnetwork_id: [u8; SUBNETWORK_ID_SIZE],
+    gas: u64,
+    payload: Vec<u8>,
+    mass: u64,
+}
+
+impl ValidatedTransaction {
+    /// Validate a transaction, reporting every invalid field
+    ///
+    /// Field paths are relative to `prefix`, e.g.
+    /// `transaction.inputs[0].signatureScript`.
+    pub fn validate(tx: &RpcTransaction, prefix: &str) -> Result<Self, RpcError> {
+        let mut errors = Vec::new();
+        let mut error = |field: String, message: String| {
+            errors.push(FieldError {
+                field: format!("{}.{}", prefix, field),
+                message,
+            })
+        };
+
+        // The maximum fits in a u16, so anything below it does too
+        let version = if tx.version > MAX_TRANSACTION_VERSION as u32 {
+            error(
+                "version".into(),
+                format!(
+                    "Unsupported version {}, expected at most {}",
+                    tx.version, MAX_TRANSACTION_VERSION
+                ),
+            );
+            0
+        } else {
+            tx.version as u16
+        };
+
+        if tx.inputs.is_empty() {
+            error("inputs".into(), "At least one input is required".into());
+        }
+
+        let mut inputs = Vec::with_capacity(tx.inputs.len());
+        let mut spent = HashMap::new();
+        for (i, input) in tx.inputs.iter().enumerate() {
+            let outpoint = input.previous_outpoint.clone().unwrap_or_default();
+            let transaction_id = match decode_fixed(&outpoint.transaction_id) {
+                Ok(transaction_id) => {
+                    if let Some(first) = spent.insert((transaction_id, outpoint.index), i) {
+                        error(
+                            format!("inputs[{}].previousOutpoint", i),
+                            format!("Spends the same outpoint as inputs[{}]", first),
+                        );
+                    }
+                    transaction_id
+                }
+                Err(message) => {
+                    error(
+                        format!("inputs[{}].previousOutpoint.transactionId", i),
+                        message,
+                    );
+                    [0; HASH_SIZE]
+                }
+            };
+
+            let signature_script = hex::decode(&input.signature_script).unwrap_or_else(|e| {
+                error(
+                    format!("inputs[{}].signatureScript", i),
+                    format!("Invalid hex: {}", e),
+                );
+                Vec::new()
+            });
+            let sig_op_count = u8::try_from(input.sig_op_count).unwrap_or_else(|_| {
+                error(
+                    format!("inputs[{}].sigOpCount", i),
+                    format!("Must be at most {}", u8::MAX),
+                );
+                0
+            });
+
+            inputs.push(Input {
+                transaction_id,
+                index: outpoint.index,
+                signature_script,
+                sequence: input.sequence,
+                sig_op_count,
+            });
+        }
+
+        let mut outputs = Vec::with_capacity(tx.outputs.len());
+        let mut total: u64 = 0;
+        for (i, output) in tx.outputs.iter().enumerate() {
+            if output.amount == 0 {
+                error(format!("outputs[{}].amount", i), "Must be positive".into());
+            }
+            total = total.saturating_add(output.amount);
+
+            let script_public_key = output.script_public_key.clone().unwrap_or_default();
+            let script_version = if script_public_key.version > MAX_SCRIPT_PUBLIC_KEY_VERSION as u32
+            {
+                error(
+                    format!("outputs[{}].scriptPublicKey.version", i),
+                    format!(
+                        "Unsupported script version {}, expected at most {}",
+                        script_public_key.version, MAX_SCRIPT_PUBLIC_KEY_VERSION
+                    ),
+                );
+                0
+            } else {
+                script_public_key.version as u16
+            };
+            let script = hex::decode(&script_public_key.script_public_key).unwrap_or_else(|e| {
+                error(
+                    format!("outputs[{}].scriptPublicKey.scriptPublicKey", i),
+                    format!("Invalid hex: {}", e),
+                );
+                Vec::new()
+            });
+
+            outputs.push(Output {
+                amount: output.amount,
+                script_version,
+                script,
+            });
+        }
+        if total > MAX_SOMPI {
+            error(
+                "outputs".into(),
+                format!("Total amount exceeds the maximum of {} sompi", MAX_SOMPI),
+            );
+        }
+
+        let subnetwork_id = decode_fixed(&tx.subnetwork_id).unwrap_or_else(|message| {
+            error("subnetworkId".into(), message);
+            NATIVE_SUBNETWORK_ID
+        });
+        if subnetwork_id == NATIVE_SUBNETWORK_ID && tx.gas > 0 {
+            error("gas".into(), "Must be 0 on the native subnetwork".into());
+        }
+
+        let payload = hex::decode(&tx.payload).unwrap_or_else(|e| {
+            error("payload".into(), format!("Invalid hex: {}", e));
+            Vec::new()
+        });
+
+        if !errors.is_empty() {
+            return Err(RpcError::Validation(errors));
+        }
+
+        Ok(Self {
+            version,
+            inputs,
+            outputs,
+            lock_time: tx.lock_time,
+            subnetwork_id,
+            gas: tx.gas,
+            payload,
+            mass: tx.mass,
+        })
+    }
+
+    /// Transaction ID; signature scripts are left out, so it is fixed
+    /// before the transaction is signed
+    pub fn id(&self) -> String {
+        let is_coinbase = self.subnetwork_id == COINBASE_SUBNETWORK_ID;
+        self.digest(b"TransactionID", is_coinbase, false)
+    }
+
+    /// Transaction hash, covering signature scripts and committed mass
+    pub fn hash(&self) -> String {
+        self.digest(b"TransactionHash", true, true)
+    }
+
+    /// Mass estimate from size, output scripts and signature operations
+    ///
+    /// Storage mass depends on the amounts of the spent UTXOs and is not
+    /// included, so the node may charge more for transactions that split
+    /// small amounts.
+    pub fn estimated_mass(&self) -> u64 {
+        let size = self.serialized_size();
+
+        let script_public_key_size: u64 = self
+            .outputs
+            .iter()
+            .map(|output| 2 + output.script.len() as u64)
+            .sum();
+        let sig_ops: u64 = self
+            .inputs
+            .iter()
+            .map(|input| input.sig_op_count as u64)
+            .sum();
+
+        let compute_mass = size * MASS_PER_TX_BYTE
+            + script_public_key_size * MASS_PER_SCRIPT_PUB_KEY_BYTE
+            + sig_ops * MASS_PER_SIG_OP;
+        let transient_mass = size * TRANSIENT_BYTE_TO_MASS_FACTOR;
+
+        compute_mass.max(transient_mass)
+    }
+
+    /// Proto message with normalized (lowercase) hex fields
+    pub fn to_proto(&self) -> RpcTransaction {
+        RpcTransaction {
+            version: self.version as u32,
+            inputs: self
+                .inputs
+                .iter()
+                .map(|input| RpcTransactionInput {
+                    previous_outpoint: Some(RpcOutpoint {
+                        transaction_id: hex::encode(input.transaction_id),
+                        index: input.index,
+                    }),
+                    signature_script: hex::encode(&input.signature_script),
+                    sequence: input.sequence,
+                    sig_op_count: input.sig_op_count as u32,
+                    verbose_data: None,
+                })
+                .collect(),
+            outputs: self
+                .outputs
+                .iter()
+                .map(|output| RpcTransactionOutput {
+                    amount: output.amount,
+                    script_public_key: Some(RpcScriptPublicKey {
+                        script_public_key: hex::encode(&output.script),
+                        version: output.script_version as u32,
+                    }),
+                    verbose_data: None,
+                })
+                .collect(),
+            lock_time: self.lock_time,
+            subnetwork_id: hex::encode(self.subnetwork_id),
+            gas: self.gas,
+            payload: hex::encode(&self.payload),
+            mass: self.mass,
+            verbose_data: None,
+        }
+    }
+
+    /// Keyed blake2b-256 over the node's transaction encoding
+    fn digest(&self, key: &[u8], include_signature_scripts: bool, include_mass: bool) -> String {
+        let mut state = Params::new().hash_length(HASH_SIZE).key(key).to_state();
+
+        state.update(&self.version.to_le_bytes());
+        write_len(&mut state, self.inputs.len());
+        for input in &self.inputs {
+            state.update(&input.transaction_id);
+            state.update(&input.index.to_le_bytes());
+            if include_signature_scripts {
+                write_var_bytes(&mut state, &input.signature_script);
+                state.update(&[input.sig_op_count]);
+            } else {
+                write_var_bytes(&mut state, &[]);
+            }
+            state.update(&input.sequence.to_le_bytes());
+        }
+
+        write_len(&mut state, self.outputs.len());
+        for output in &self.outputs {
+            state.update(&output.amount.to_le_bytes());
+            state.update(&output.script_version.to_le_bytes());
+            write_var_bytes(&mut state, &output.script);
+        }
+
+        state.update(&self.lock_time.to_le_bytes());
+        state.update(&self.subnetwork_id);
+        state.update(&self.gas.to_le_bytes());
+        write_var_bytes(&mut state, &self.payload);
+
+        // A zero mass is left out, keeping hashes of uncommitted transactions stable
+        if include_mass && self.mass > 0 {
+            state.update(&self.mass.to_le_bytes());
+        }
+
+        state.finalize().to_hex().to_string()
+    }
+
+    /// Serialized size as the node's mass calculator counts it
+    fn serialized_size(&self) -> u64 {
+        let inputs: u64 = self
+            .inputs
+            .iter()
+            .map(|input| HASH_SIZE as u64 + 4 + 8 + input.signature_script.len() as u64 + 8)
+            .sum();
+        let outputs: u64 = self
+            .outputs
+            .iter()
+            .map(|output| 8 + 2 + 8 + output.script.len() as u64)
+            .sum();
+
+        2 // version
+            + 8 + inputs
+            + 8 + outputs
+            + 8 // lock time
+            + SUBNETWORK_ID_SIZE as u64
+            + 8 // gas
+            + HASH_SIZE as u64 // payload hash
+            + 8 + self.payload.len() as u64
+    }
+}
+
+fn write_len(state: &mut State, len: usize) {
+    state.update(&(len as u64).to_le_bytes());
+}
+
+fn write_var_bytes(state: &mut State, bytes: &[u8]) {
+    write_len(state, bytes.len());
+    state.update(bytes);
+}
+
+/// Decode a fixed-size hex field
+fn decode_fixed<const N: usize>(value: &str) -> Result<[u8; N], String> {
+    let bytes = hex::decode(value).map_err(|e| format!("Invalid hex: {}", e))?;
+    bytes
+        .try_into()
+        .map_err(|_| format!("Expected {} hex characters", N * 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OP_DATA_32: u8 = 0x20;
+    const OP_CHECKSIG: u8 = 0xac;
+
+    fn transaction(version: u16, inputs: Vec<Input>, outputs: Vec<Output>) -> ValidatedTransaction {
+        ValidatedTransaction {
+            version,
+            inputs,
+            outputs,
+            lock_time: 0,
+            subnetwork_id: NATIVE_SUBNETWORK_ID,
+            gas: 0,
+            payload: Vec::new(),
+            mass: 0,
+        }
+    }
+
+    fn input(signature_script: Vec<u8>, sig_op_count: u8) -> Input {
+        Input {
+            transaction_id: [0; HASH_SIZE],
+            index: 2,
+            signature_script,
+            sequence: 7,
+            sig_op_count,
+        }
+    }
+
+    fn pay_to_pubkey(amount: u64) -> Output {
+        let mut script = vec![OP_DATA_32];
+        script.extend_from_slice(&[0x11; 32]);
+        script.push(OP_CHECKSIG);
+        Output {
+            amount,
+            script_version: 0,
+            script,
+        }
+    }
+
+    // Vectors from rusty-kaspa's consensus transaction hashing tests
+    #[test]
+    fn empty_transaction_id_and_hash() {
+        let tx = transaction(0, Vec::new(), Vec::new());
+        assert_eq!(
+            tx.id(),
+            "2c18d5e59ca8fc4c23d9560da3bf738a8f40935c11c162017fbf2c907b7e665c"
+        );
+        assert_eq!(
+            tx.hash(),
+            "c9e29784564c269ce2faaffd3487cb4684383018ace11133de082dce4bb88b0b"
+        );
+    }
+
+    #[test]
+    fn transaction_with_input_id_and_hash() {
+        let tx = transaction(1, vec![input(vec![1, 2], 5)], Vec::new());
+        assert_eq!(
+            tx.id(),
+            "dafa415216d26130a899422203559c809d3efe72e20d48505fb2f08787bc4f49"
+        );
+        assert_eq!(
+            tx.hash(),
+            "e4045023768d98839c976918f80c9419c6a93003724eda97f7c61a5b68de851b"
+        );
+    }
+
+    #[test]
+    fn id_ignores_signature_scripts() {
+        let unsigned = transaction(0, vec![input(Vec::new(), 1)], vec![pay_to_pubkey(1000)]);
+        let signed = transaction(0, vec![input(vec![0x41; 66], 1)], vec![pay_to_pubkey(1000)]);
+        assert_eq!(unsigned.id(), signed.id());
+        assert_ne!(unsigned.hash(), signed.hash());
+    }
+
+    #[test]
+    fn hash_commits_to_nonzero_mass() {
+        let mut tx = transaction(0, vec![input(vec![1], 1)], vec![pay_to_pubkey(1000)]);
+        let uncommitted = tx.hash();
+        tx.mass = 2036;
+        assert_ne!(tx.hash(), uncommitted);
+        assert_eq!(
+            tx.id(),
+            transaction(0, vec![input(vec![1], 1)], vec![pay_to_pubkey(1000)]).id()
+        );
+    }
+
+    // A Schnorr-signed transfer with change, the node's most common shape
+    #[test]
+    fn standard_transfer_mass() {
+        let signature_script = vec![0x41; 66];
+        let tx = transaction(
+            0,
+            vec![input(signature_script, 1)],
+            vec![pay_to_pubkey(100_000_000), pay_to_pubkey(50_000_000)],
+        );
+        assert_eq!(tx.serialized_size(), 316);
+        assert_eq!(tx.estimated_mass(), 2036);
+    }
+
+    #[test]
+    fn transient_mass_bounds_large_payloads() {
+        let mut tx = transaction(0, vec![input(vec![0x41; 66], 1)], vec![pay_to_pubkey(1000)]);
+        tx.payload = vec![0; 10_000];
+        assert_eq!(
+            tx.estimated_mass(),
+            tx.serialized_size() * TRANSIENT_BYTE_TO_MASS_FACTOR
+        );
+    }
+
+    #[test]
+    fn validate_reports_every_invalid_field() {
+        let tx = RpcTransaction {
+            version: 1,
+            inputs: vec![RpcTransactionInput {
+                previous_outpoint: Some(RpcOutpoint {
+                    transaction_id: "zz".into(),
+                    index: 0,
+                }),
+                signature_script: "0".into(),
+                sequence: 0,
+                sig_op_count: 1,
+                verbose_data: None,
+            }],
+            outputs: vec![RpcTransactionOutput {
+                amount: 0,
+                script_public_key: Some(RpcScriptPublicKey {
+                    script_public_key: "20".into(),
+                    version: 0,
+                }),
+                verbose_data: None,
+            }],
+            subnetwork_id: hex::encode(NATIVE_SUBNETWORK_ID),
+            ..Default::default()
+        };
+
+        let Err(RpcError::Validation(errors)) = ValidatedTransaction::validate(&tx, "transaction")
+        else {
+            panic!("expected validation errors");
+        };
+        let fields: Vec<_> = errors.iter().map(|e| e.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            [
+                "transaction.version",
+                "transaction.inputs[0].previousOutpoint.transactionId",
+                "transaction.inputs[0].signatureScript",
+                "transaction.outputs[0].amount",
+            ]
+        );
+    }
+
+    #[test]
+    fn to_proto_round_trips() {
+        let tx = transaction(0, vec![input(vec![0xab; 3], 1)], vec![pay_to_pubkey(1000)]);
+        let validated = ValidatedTransaction::validate(&tx.to_proto(), "transaction").unwrap();
+        assert_eq!(validated.id(), tx.id());
+        assert_eq!(validated.hash(), tx.hash());
+    }
+}
@@ -0,0 +1,237 @@
+use crate::{client::KaspaClient, error::RpcError, metrics};
+use serde::Serialize;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+/// Boxed future returned by a call dispatched through the pool
+pub type UpstreamFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, RpcError>> + Send + 'a>>;
+
+/// Upper bound on a single health probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Last observed health of one upstream node
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpstreamHealth {
+    pub healthy: bool,
+    pub synced: bool,
+    /// Network the node reports, e.g. `testnet-10`
+    pub network: Option<String>,
+    /// Whether the node runs on another network than `KASPA_NETWORK`;
+    /// such a node never serves calls
+    pub wrong_network: bool,
+    pub latency_ms: Option<f64>,
+    pub last_error: Option<String>,
+    pub last_checked_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// A single kaspad node with its health state
+pub struct Upstream {
+    url: String,
+    // Network ID the node must report, e.g. `testnet-10`
+    network: String,
+    client: Arc<KaspaClient>,
+    health: RwLock<UpstreamHealth>,
+}
+
+impl Upstream {
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn client(&self) -> Arc<KaspaClient> {
+        self.client.clone()
+    }
+
+    pub fn health(&self) -> UpstreamHealth {
+        self.health.read().unwrap().clone()
+    }
+
+    /// Ping the node and check its network and sync status
+    async fn probe(&self) {
+        let start = Instant::now();
+
+        let result = tokio::time::timeout(PROBE_TIMEOUT, async {
+            self.client.ping().await?;
+            let network = self.client.get_server_info().await?.network_id;
+            let status = self.client.get_sync_status().await?;
+            Ok((network, status))
+        })
+        .await
+        .unwrap_or_else(|_| Err(RpcError::Connection("Health probe timed out".into())));
+
+        let latency_ms = start.elapsed().as_secs_f64() * 1000.0;
+
+        let mut health = self.health.write().unwrap();
+        health.last_checked_at = Some(chrono::Utc::now());
+        match result {
+            Ok((network, status)) => {
+                // Testnets share a network type, so the full ID is compared
+                let network_error = (!network.eq_ignore_ascii_case(&self.network)).then(|| {
+                    format!(
+                        "Node runs on {} but the service runs on {}",
+                        network, self.network
+                    )
+                });
+                if let Some(e) = &network_error {
+                    tracing::error!(upstream = %self.url, "Taking node out of rotation: {}", e);
+                }
+                health.wrong_network = network_error.is_some();
+                health.healthy = !health.wrong_network;
+                health.synced = status.is_synced && !health.wrong_network;
+                health.network = Some(network);
+                health.latency_ms = Some(latency_ms);
+                health.last_error = network_error;
+            }
+            Err(e) => {
+                tracing::warn!(upstream = %self.url, "Health probe failed: {}", e);
+                health.healthy = false;
+                health.synced = false;
+                health.latency_ms = None;
+                health.last_error = Some(e.to_string());
+            }
+        }
+        metrics::record_upstream_health(&self.url, &health);
+    }
+
+    /// Take the node out of rotation until the next successful probe
+    fn mark_unhealthy(&self, error: &RpcError) {
+        let mut health = self.health.write().unwrap();
+        health.healthy = false;
+        health.synced = false;
+        health.last_error = Some(error.to_string());
+        metrics::record_upstream_health(&self.url, &health);
+    }
+}
+
+/// Pool of kaspad nodes with health-aware failover
+///
+/// Nodes are preferred in configuration order: the first healthy,
+/// synced node serves a call, nodes on another network than the
+/// configured one never do, and connection failures and timeouts
+/// fall through to the next candidate. Calls with side effects only
+/// fall through when they never reached the node; the client reports
+/// those lost after sending as `Unconfirmed`.
+pub struct UpstreamPool {
+    upstreams: Vec<Arc<Upstream>>,
+}
+
+impl UpstreamPool {
+    /// Create clients for every configured node; connections are made lazily
+    pub fn new(urls: &[String], network: &str) -> Result<Self, RpcError> {
+        if urls.is_empty() {
+            return Err(RpcError::Internal("No upstream nodes configured".into()));
+        }
+
+        let mut upstreams = Vec::with_capacity(urls.len());
+        for url in urls {
+            let client = KaspaClient::new(url)?;
+
+            // Out of rotation until the first probe succeeds
+            let health = UpstreamHealth {
+                healthy: false,
+                synced: false,
+                network: None,
+                wrong_network: false,
+                latency_ms: None,
+                last_error: None,
+                last_checked_at: None,
+            };
+            metrics::record_upstream_health(url, &health);
+
+            upstreams.push(Arc::new(Upstream {
+                url: url.clone(),
+                network: network.to_string(),
+                client: Arc::new(client),
+                health: RwLock::new(health),
+            }));
+        }
+
+        Ok(Self { upstreams })
+    }
+
+    /// Probe every node on a fixed interval in the background
+    pub fn spawn_health_checks(&self, interval: Duration) {
+        for upstream in &self.upstreams {
+            let upstream = upstream.clone();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(interval);
+                loop {
+                    ticker.tick().await;
+                    upstream.probe().await;
+                }
+            });
+        }
+    }
+
+    pub fn upstreams(&self) -> &[Arc<Upstream>] {
+        &self.upstreams
+    }
+
+    /// Nodes ordered by preference: synced, then healthy, then the rest;
+    /// nodes on the wrong network are left out
+    fn candidates(&self) -> Vec<Arc<Upstream>> {
+        let mut ranked: Vec<_> = self
+            .upstreams
+            .iter()
+            .filter_map(|upstream| {
+                let health = upstream.health();
+                let rank = match (health.wrong_network, health.healthy, health.synced) {
+                    (true, _, _) => return None,
+                    (false, true, true) => 0,
+                    (false, true, false) => 1,
+                    _ => 2,
+                };
+                Some((rank, upstream.clone()))
+            })
+            .collect();
+        // Stable sort keeps configuration order within a rank
+        ranked.sort_by_key(|(rank, _)| *rank);
+        ranked.into_iter().map(|(_, upstream)| upstream).collect()
+    }
+
+    /// Best node for long-lived subscriptions
+    ///
+    /// Only when every node is on the wrong network does this return one
+    /// of them; `serves` then tells the caller to keep looking.
+    pub fn select(&self) -> Arc<KaspaClient> {
+        self.candidates()
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| self.upstreams[0].clone())
+            .client()
+    }
+
+    /// Whether `client` belongs to a node on the pool's network
+    pub fn serves(&self, client: &Arc<KaspaClient>) -> bool {
+        self.upstreams
+            .iter()
+            .find(|upstream| Arc::ptr_eq(&upstream.client, client))
+            .is_some_and(|upstream| !upstream.health().wrong_network)
+    }
+
+    /// Run a call against the best node, failing over on connection errors
+    /// and timeouts
+    pub async fn call<T, F>(&self, f: F) -> Result<T, RpcError>
+    where
+        F: for<'a> Fn(&'a KaspaClient) -> UpstreamFuture<'a, T>,
+    {
+        let mut last_error = None;
+
+        for upstream in self.candidates() {
+            match f(&upstream.client).await {
+                Err(error @ (RpcError::Connection(_) | RpcError::Timeout(_))) => {
+                    tracing::warn!(upstream = %upstream.url, "Failing over: {}", error);
+                    upstream.mark_unhealthy(&error);
+                    metrics::record_upstream_failover(&upstream.url);
+                    last_error = Some(error);
+                }
+                result => return result,
+            }
+        }
+
+        Err(last_error.unwrap_or_else(|| RpcError::Connection("No upstream available".into())))
+    }
+}
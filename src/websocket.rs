@@ -1,13 +1,25 @@
-use crate::{error::RpcError, models::SubscribeUTXORequest, AppState};
+use crate::{
+    address,
+    error::{ErrorBody, RpcError},
+    models::{WsCommand, WsTopic},
+    request_id::{self, RequestId},
+    subscriptions::{Event, Subscription, Topic, UtxoChange},
+    AppState,
+};
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
         Query, State,
     },
     response::{IntoResponse, Response},
+    Extension,
 };
 use serde::Deserialize;
-use tokio_stream::StreamExt;
+use serde_json::Value;
+use tokio_stream::{StreamExt, StreamMap};
+
+/// Upper bound on concurrent subscriptions held by one socket
+const MAX_SUBSCRIPTIONS_PER_SOCKET: usize = 64;
 
 #[derive(Debug, Deserialize)]
 pub struct WsQuery {
@@ -19,6 +31,7 @@ pub async fn subscribe_utxo(
     ws: WebSocketUpgrade,
     Query(query): Query<WsQuery>,
     State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
 ) -> Response {
     let addresses: Vec<String> = query
         .addresses
@@ -34,8 +47,14 @@ pub async fn subscribe_utxo(
         )
             .into_response();
     }
+    if let Err(e) = address::validate("addresses", &addresses) {
+        return e.into_response();
+    }
 
-    ws.on_upgrade(move |socket| handle_utxo_subscription(socket, addresses, state))
+    // Error frames carry the ID of the upgrade request
+    ws.on_upgrade(move |socket| {
+        request_id::scope(request_id, handle_utxo_subscription(socket, addresses, state))
+    })
 }
 
 async fn handle_utxo_subscription(
@@ -45,23 +64,18 @@ async fn handle_utxo_subscription(
 ) {
     tracing::info!("New UTXO subscription for {} addresses", addresses.len());
 
-    // Subscribe to Kaspa UTXO changes
-    let stream_result = state
-        .kaspa_client
-        .subscribe_utxo_changes(addresses.clone())
+    // Join the shared upstream UTXO subscription
+    let mut connection = state.subscriptions.connection_state();
+    let subscription = state
+        .subscriptions
+        .subscribe(Topic::UtxosChanged, addresses.clone())
         .await;
 
-    let mut stream = match stream_result {
-        Ok(s) => s,
+    let mut subscription = match subscription {
+        Ok(subscription) => subscription,
         Err(e) => {
-            let _ = socket
-                .send(Message::Text(
-                    serde_json::json!({
-                        "error": format!("Failed to subscribe: {}", e)
-                    })
-                    .to_string(),
-                ))
-                .await;
+            let error = serde_json::json!(ErrorBody::from(e));
+            let _ = socket.send(Message::Text(error.to_string())).await;
             let _ = socket.close().await;
             return;
         }
@@ -79,73 +93,350 @@ async fn handle_utxo_subscription(
         .await;
 
     // Forward UTXO change notifications to WebSocket client
-    while let Some(response) = stream.next().await {
-        match response {
-            Ok(kaspad_response) => {
-                // Check if it's a UTXO changed notification
-                if let Some(crate::client::proto::kaspad_response::Payload::UtxosChangedNotification(
-                    notification,
-                )) = kaspad_response.payload
-                {
-                    let message = serde_json::json!({
-                        "type": "utxo_changed",
-                        "added": notification.added.iter().map(|entry| {
-                            serde_json::json!({
-                                "address": entry.address,
-                                "outpoint": entry.outpoint.as_ref().map(|op| {
-                                    serde_json::json!({
-                                        "transaction_id": op.transaction_id,
-                                        "index": op.index,
-                                    })
-                                }),
-                                "utxo_entry": entry.utxo_entry.as_ref().map(|utxo| {
-                                    serde_json::json!({
-                                        "amount": utxo.amount,
-                                        "script_public_key": utxo.script_public_key.as_ref().map(|s| s.script_public_key.clone()),
-                                        "block_daa_score": utxo.block_daa_score,
-                                        "is_coinbase": utxo.is_coinbase,
-                                    })
-                                }),
-                            })
-                        }).collect::<Vec<_>>(),
-                        "removed": notification.removed.iter().map(|entry| {
-                            serde_json::json!({
-                                "address": entry.address,
-                                "outpoint": entry.outpoint.as_ref().map(|op| {
-                                    serde_json::json!({
-                                        "transaction_id": op.transaction_id,
-                                        "index": op.index,
-                                    })
-                                }),
-                            })
-                        }).collect::<Vec<_>>(),
-                    });
-
-                    if socket
-                        .send(Message::Text(message.to_string()))
-                        .await
-                        .is_err()
-                    {
-                        tracing::warn!("Client disconnected");
-                        break;
+    loop {
+        tokio::select! {
+            event = subscription.recv() => {
+                let message = match event {
+                    Some(event @ (Event::UtxosChanged(_) | Event::Resync(_))) => {
+                        event_json(&event)
                     }
+                    _ => break,
+                };
+
+                if socket
+                    .send(Message::Text(message.to_string()))
+                    .await
+                    .is_err()
+                {
+                    tracing::warn!("Client disconnected");
+                    break;
+                }
+            },
+            changed = connection.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                // Subscriptions are re-registered upstream after reconnecting
+                let status = if *connection.borrow_and_update() {
+                    "resubscribed"
+                } else {
+                    "reconnecting"
+                };
+                if socket
+                    .send(Message::Text(serde_json::json!({ "status": status }).to_string()))
+                    .await
+                    .is_err()
+                {
+                    tracing::warn!("Client disconnected");
+                    break;
+                }
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => {
+                    tracing::info!("Client disconnected");
+                    break;
                 }
-            }
-            Err(e) => {
-                tracing::error!("Stream error: {}", e);
-                let _ = socket
-                    .send(Message::Text(
-                        serde_json::json!({
-                            "error": format!("Stream error: {}", e)
-                        })
-                        .to_string(),
-                    ))
-                    .await;
-                break;
-            }
+                Some(Ok(_)) => {}
+            },
         }
     }
 
     let _ = socket.close().await;
     tracing::info!("UTXO subscription closed");
 }
+
+/// Multiplexed WebSocket endpoint driven by JSON commands
+///
+/// Clients open any number of subscriptions on one socket and change
+/// their address sets at runtime with `subscribe`, `unsubscribe`,
+/// `add_addresses` and `remove_addresses` commands. Every command is
+/// answered with an `ack` or an `error` carrying the subscription ID.
+pub async fn subscribe(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Extension(request_id): Extension<RequestId>,
+) -> Response {
+    ws.on_upgrade(move |socket| request_id::scope(request_id, handle_session(socket, state)))
+}
+
+async fn handle_session(mut socket: WebSocket, state: AppState) {
+    tracing::info!("New multiplexed WebSocket session");
+
+    let mut subscriptions: StreamMap<String, Subscription> = StreamMap::new();
+    let mut connection = state.subscriptions.connection_state();
+
+    loop {
+        let message = tokio::select! {
+            Some((id, event)) = subscriptions.next(), if !subscriptions.is_empty() => {
+                let mut message = event_json(&event);
+                message["subscription"] = id.into();
+                message
+            },
+            changed = connection.changed() => {
+                if changed.is_err() {
+                    break;
+                }
+                // Subscriptions are re-registered upstream after reconnecting
+                let status = if *connection.borrow_and_update() {
+                    "resubscribed"
+                } else {
+                    "reconnecting"
+                };
+                serde_json::json!({ "type": "status", "status": status })
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    handle_command(&text, &mut subscriptions, &state).await
+                }
+                Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+
+        if socket
+            .send(Message::Text(message.to_string()))
+            .await
+            .is_err()
+        {
+            break;
+        }
+    }
+
+    // Dropping the subscriptions releases their addresses upstream
+    drop(subscriptions);
+    let _ = socket.close().await;
+    tracing::info!("Multiplexed WebSocket session closed");
+}
+
+/// Apply one client command and build its ack or error reply
+async fn handle_command(
+    text: &str,
+    subscriptions: &mut StreamMap<String, Subscription>,
+    state: &AppState,
+) -> Value {
+    let command: WsCommand = match serde_json::from_str(text) {
+        Ok(command) => command,
+        Err(e) => {
+            let error = RpcError::BadRequest(format!("Invalid command: {}", e));
+            return error_frame(None, None, error);
+        }
+    };
+
+    let op = command.op();
+    let result = match command {
+        WsCommand::Subscribe {
+            id,
+            topic,
+            addresses,
+            include_accepted_transaction_ids,
+        } => {
+            let id = id.unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+            let topic = match topic {
+                WsTopic::UtxosChanged => Topic::UtxosChanged,
+                WsTopic::BlockAdded => Topic::BlockAdded,
+                WsTopic::VirtualChainChanged => Topic::VirtualChainChanged {
+                    include_accepted_transaction_ids,
+                },
+                WsTopic::SinkBlueScoreChanged => Topic::SinkBlueScoreChanged,
+                WsTopic::VirtualDaaScoreChanged => Topic::VirtualDaaScoreChanged,
+            };
+            subscribe_topic(&id, topic, addresses, subscriptions, state)
+                .await
+                .map(|_| id)
+        }
+        WsCommand::Unsubscribe { id } => match subscriptions.remove(&id) {
+            Some(_) => Ok(id),
+            None => Err((id, RpcError::NotFound("Unknown subscription".into()))),
+        },
+        WsCommand::AddAddresses { id, addresses } => {
+            update_addresses(&id, addresses, true, subscriptions)
+                .await
+                .map(|_| id)
+        }
+        WsCommand::RemoveAddresses { id, addresses } => {
+            update_addresses(&id, addresses, false, subscriptions)
+                .await
+                .map(|_| id)
+        }
+    };
+
+    match result {
+        Ok(id) => serde_json::json!({ "type": "ack", "op": op, "id": id }),
+        Err((id, error)) => error_frame(Some(op), Some(id), error),
+    }
+}
+
+/// Error reply: the REST error body, tagged with the command it answers
+fn error_frame(op: Option<&str>, id: Option<String>, error: RpcError) -> Value {
+    let mut frame = serde_json::json!(ErrorBody::from(error));
+    frame["type"] = "error".into();
+    if let Some(op) = op {
+        frame["op"] = op.into();
+    }
+    if let Some(id) = id {
+        frame["id"] = id.into();
+    }
+    frame
+}
+
+async fn subscribe_topic(
+    id: &str,
+    topic: Topic,
+    addresses: Vec<String>,
+    subscriptions: &mut StreamMap<String, Subscription>,
+    state: &AppState,
+) -> Result<(), (String, RpcError)> {
+    let fail = |error: RpcError| Err((id.to_string(), error));
+
+    if subscriptions.contains_key(id) {
+        return fail(RpcError::BadRequest("Subscription ID already in use".into()));
+    }
+    if subscriptions.len() >= MAX_SUBSCRIPTIONS_PER_SOCKET {
+        return fail(RpcError::BadRequest(format!(
+            "At most {} subscriptions per connection",
+            MAX_SUBSCRIPTIONS_PER_SOCKET
+        )));
+    }
+
+    let addresses = clean_addresses(addresses);
+    // Only UTXO subscriptions are scoped to addresses
+    let takes_addresses = topic == Topic::UtxosChanged;
+    if takes_addresses && addresses.is_empty() {
+        return fail(RpcError::BadRequest("No addresses provided".into()));
+    }
+    if !takes_addresses && !addresses.is_empty() {
+        return fail(RpcError::BadRequest("Topic does not take addresses".into()));
+    }
+    if let Err(e) = check_addresses(&addresses) {
+        return fail(e);
+    }
+
+    match state.subscriptions.subscribe(topic, addresses).await {
+        Ok(subscription) => {
+            subscriptions.insert(id.to_string(), subscription);
+            Ok(())
+        }
+        Err(e) => fail(e),
+    }
+}
+
+async fn update_addresses(
+    id: &str,
+    addresses: Vec<String>,
+    add: bool,
+    subscriptions: &mut StreamMap<String, Subscription>,
+) -> Result<(), (String, RpcError)> {
+    let Some((_, subscription)) = subscriptions.iter_mut().find(|(key, _)| key.as_str() == id)
+    else {
+        return Err((
+            id.to_string(),
+            RpcError::NotFound("Unknown subscription".into()),
+        ));
+    };
+
+    let addresses = clean_addresses(addresses);
+    if addresses.is_empty() {
+        return Err((
+            id.to_string(),
+            RpcError::BadRequest("No addresses provided".into()),
+        ));
+    }
+
+    // Addresses being removed only need to match what was added
+    if add {
+        check_addresses(&addresses).map_err(|e| (id.to_string(), e))?;
+    }
+
+    let result = if add {
+        subscription.add_addresses(addresses).await
+    } else {
+        subscription.remove_addresses(addresses).await
+    };
+    result.map_err(|e| (id.to_string(), e))
+}
+
+/// Trim addresses and drop empty entries
+fn clean_addresses(addresses: Vec<String>) -> Vec<String> {
+    addresses
+        .into_iter()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+/// Reject the first address that does not decode or is for another network
+fn check_addresses(addresses: &[String]) -> Result<(), RpcError> {
+    addresses.iter().try_for_each(|addr| {
+        address::check(addr)
+            .map(|_| ())
+            .map_err(|e| RpcError::BadRequest(format!("Invalid address '{}': {}", addr, e)))
+    })
+}
+
+/// Notification message for one subscription event
+fn event_json(event: &Event) -> Value {
+    match event {
+        Event::UtxosChanged(change) => {
+            let mut message = utxo_change_json(change);
+            message["type"] = "utxo_changed".into();
+            message
+        }
+        Event::BlockAdded(block) => serde_json::json!({
+            "type": "block_added",
+            "block": block.as_ref(),
+        }),
+        Event::VirtualChainChanged(changed) => {
+            let mut message = serde_json::to_value(changed.as_ref()).unwrap_or_default();
+            message["type"] = "virtual_chain_changed".into();
+            message
+        }
+        Event::SinkBlueScoreChanged(sink_blue_score) => serde_json::json!({
+            "type": "sink_blue_score_changed",
+            "sinkBlueScore": sink_blue_score,
+        }),
+        Event::VirtualDaaScoreChanged(virtual_daa_score) => serde_json::json!({
+            "type": "virtual_daa_score_changed",
+            "virtualDaaScore": virtual_daa_score,
+        }),
+        Event::Resync(dropped) => serde_json::json!({
+            "type": "resync",
+            "dropped": dropped,
+        }),
+    }
+}
+
+/// JSON body shared by every UTXO change message
+fn utxo_change_json(change: &UtxoChange) -> Value {
+    serde_json::json!({
+        "added": change.added.iter().map(|entry| {
+            serde_json::json!({
+                "address": entry.address,
+                "outpoint": entry.outpoint.as_ref().map(|op| {
+                    serde_json::json!({
+                        "transactionId": op.transaction_id,
+                        "index": op.index,
+                    })
+                }),
+                "utxoEntry": entry.utxo_entry.as_ref().map(|utxo| {
+                    serde_json::json!({
+                        "amount": utxo.amount,
+                        "scriptPublicKey": utxo.script_public_key.as_ref().map(|s| s.script_public_key.clone()),
+                        "blockDaaScore": utxo.block_daa_score,
+                        "isCoinbase": utxo.is_coinbase,
+                    })
+                }),
+            })
+        }).collect::<Vec<_>>(),
+        "removed": change.removed.iter().map(|entry| {
+            serde_json::json!({
+                "address": entry.address,
+                "outpoint": entry.outpoint.as_ref().map(|op| {
+                    serde_json::json!({
+                        "transactionId": op.transaction_id,
+                        "index": op.index,
+                    })
+                }),
+            })
+        }).collect::<Vec<_>>(),
+    })
+}
@@ -5,10 +5,16 @@ set -e
 
 BASE_URL="${BASE_URL:-http://localhost:8080}"
 
+# Token from /auth/token; required unless the service runs with AUTH_ENABLED=false
+AUTH=()
+if [ -n "${TOKEN}" ]; then
+  AUTH=(-H "Authorization: Bearer ${TOKEN}")
+fi
+
 echo "Testing getDAGTips endpoint..."
 
 response=$(curl -s -X POST "${BASE_URL}/rpc/getDAGTips" \
-  -H "Content-Type: application/json" \
+  -H "Content-Type: application/json" "${AUTH[@]}" \
   -d "{}")
 
 echo "Response:"
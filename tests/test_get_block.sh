@@ -5,13 +5,19 @@ set -e
 
 BASE_URL="${BASE_URL:-http://localhost:8080}"
 
+# Token from /auth/token; required unless the service runs with AUTH_ENABLED=false
+AUTH=()
+if [ -n "${TOKEN}" ]; then
+  AUTH=(-H "Authorization: Bearer ${TOKEN}")
+fi
+
 echo "Testing getBlock endpoint..."
 
 # Example block hash (replace with actual testnet block hash)
 BLOCK_HASH="0000000000000000000000000000000000000000000000000000000000000001"
 
 response=$(curl -s -X POST "${BASE_URL}/rpc/getBlock" \
-  -H "Content-Type: application/json" \
+  -H "Content-Type: application/json" "${AUTH[@]}" \
   -d "{
     \"hash\": \"${BLOCK_HASH}\",
     \"includeTransactions\": true
@@ -13,12 +13,12 @@ ADDRESSES="kaspa:qztest1234567890abcdef,kaspa:qztest0987654321fedcba"
 
 # Use websocat if available, otherwise provide instructions
 if command -v websocat &> /dev/null; then
-  timeout 30 websocat "${BASE_URL}/ws/subscribeUTXO?addresses=${ADDRESSES}" || true
+  timeout 30 websocat "${BASE_URL}/ws/subscribeUTXO?addresses=${ADDRESSES}&token=${TOKEN}" || true
 else
   echo "⚠️  websocat not found. Install with: cargo install websocat"
   echo ""
   echo "Test manually with:"
-  echo "  websocat '${BASE_URL}/ws/subscribeUTXO?addresses=${ADDRESSES}'"
+  echo "  websocat '${BASE_URL}/ws/subscribeUTXO?addresses=${ADDRESSES}&token=${TOKEN}'"
   echo ""
   echo "Or use this JavaScript snippet in browser console:"
   echo ""